@@ -0,0 +1,538 @@
+//! Integrity protection of external memory with per-page authentication
+//! tags.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use digest::Mac;
+
+#[cfg(feature = "poly1305")]
+use core::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hint::black_box,
+};
+
+#[cfg(feature = "poly1305")]
+use cipher::{Iv, Key, KeyIvInit, StreamCipher, StreamCipherSeek};
+#[cfg(feature = "poly1305")]
+use poly1305::{universal_hash::KeyInit, Poly1305};
+
+#[cfg(all(feature = "poly1305", feature = "zeroize"))]
+use zeroize::Zeroize;
+
+#[cfg(feature = "poly1305")]
+use crate::compare::accumulate;
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Computing and checking authentication tags of memory pages.
+///
+/// Implemented for all keyed [`Mac`] instances, for example `hmac::Hmac`.
+/// Tag covers page index together with page data, so that pages could not
+/// be swapped. One-time authenticators must not reuse the key across pages:
+/// Poly1305 with a key derived for each page is `Poly1305Authenticator`,
+/// under `poly1305` feature.
+pub trait PageAuthenticator {
+    /// Length of single tag, in bytes.
+    fn tag_len(&self) -> usize;
+
+    /// Compute tag of the page with known index into `tag`.
+    fn compute(&self, page: usize, data: &[u8], tag: &mut [u8]);
+
+    /// Check tag of the page with known index, in constant time.
+    fn verify(&self, page: usize, data: &[u8], tag: &[u8]) -> bool;
+
+    /// Compute tags of all pages of data, for writing into sidecar region.
+    fn seal(&self, data: &[u8], page_size: usize) -> Vec<u8> {
+        let page_count = data.len().div_ceil(page_size);
+        let mut tags = vec![0; page_count * self.tag_len()];
+        for (page, (chunk, tag)) in data
+            .chunks(page_size)
+            .zip(tags.chunks_mut(self.tag_len()))
+            .enumerate()
+        {
+            self.compute(page, chunk, tag);
+        }
+        tags
+    }
+}
+
+impl<M: Mac + Clone> PageAuthenticator for M {
+    fn tag_len(&self) -> usize {
+        M::output_size()
+    }
+    fn compute(&self, page: usize, data: &[u8], tag: &mut [u8]) {
+        tag.copy_from_slice(&page_mac(self, page, data).finalize().into_bytes());
+    }
+    fn verify(&self, page: usize, data: &[u8], tag: &[u8]) -> bool {
+        page_mac(self, page, data).verify_slice(tag).is_ok()
+    }
+}
+
+fn page_mac<M: Mac + Clone>(mac: &M, page: usize, data: &[u8]) -> M {
+    let mut mac = mac.clone();
+    Mac::update(&mut mac, &(page as u64).to_le_bytes());
+    Mac::update(&mut mac, data);
+    mac
+}
+
+/// Poly1305 [`PageAuthenticator`] with one-time key of each page taken from
+/// keystream of seekable stream cipher `C`, for example ChaCha20.
+///
+/// Key of page `n` is 32 bytes of keystream at position `32 * n`, under key
+/// and nonce of the region, so that no key is used for two pages and pages
+/// could not be swapped. Region must be sealed again with a fresh nonce
+/// after any change, as two tags under the same one-time key allow forgery.
+/// Key must differ from the one used for encryption of the same region.
+///
+/// `Debug` output does not disclose key and nonce. With `zeroize` feature,
+/// key and nonce are wiped from RAM on drop.
+///
+/// # Panics
+///
+/// Computing tags panics if keystream of `C` ends before the key of the
+/// page; verification of such page fails instead.
+#[cfg(feature = "poly1305")]
+pub struct Poly1305Authenticator<C: KeyIvInit> {
+    key: Key<C>,
+    iv: Iv<C>,
+}
+
+#[cfg(feature = "poly1305")]
+impl<C> Poly1305Authenticator<C>
+where
+    C: KeyIvInit + StreamCipher + StreamCipherSeek,
+{
+    /// New authenticator, with key and nonce of the region.
+    pub fn new(key: &Key<C>, iv: &Iv<C>) -> Self {
+        Self {
+            key: key.clone(),
+            iv: iv.clone(),
+        }
+    }
+
+    /// Tag of the page, if keystream reaches the page key.
+    fn page_tag(&self, page: usize, data: &[u8]) -> Option<poly1305::Tag> {
+        let mut cipher = C::new(&self.key, &self.iv);
+        let mut page_key = poly1305::Key::default();
+        cipher
+            .try_seek((page as u64).checked_mul(page_key.len() as u64)?)
+            .ok()?;
+        cipher.try_apply_keystream(&mut page_key).ok()?;
+        let tag = Poly1305::new(&page_key).compute_unpadded(data);
+        #[cfg(feature = "zeroize")]
+        page_key.as_mut_slice().zeroize();
+        Some(tag)
+    }
+}
+
+#[cfg(feature = "poly1305")]
+impl<C> PageAuthenticator for Poly1305Authenticator<C>
+where
+    C: KeyIvInit + StreamCipher + StreamCipherSeek,
+{
+    fn tag_len(&self) -> usize {
+        poly1305::BLOCK_SIZE
+    }
+    fn compute(&self, page: usize, data: &[u8], tag: &mut [u8]) {
+        let page_tag = self
+            .page_tag(page, data)
+            .expect("keystream must cover keys of all pages");
+        tag.copy_from_slice(&page_tag);
+    }
+    fn verify(&self, page: usize, data: &[u8], tag: &[u8]) -> bool {
+        match self.page_tag(page, data) {
+            Some(page_tag) => {
+                tag.len() == page_tag.len() && black_box(accumulate(0, &page_tag, tag)) == 0
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(feature = "poly1305")]
+impl<C: KeyIvInit> Clone for Poly1305Authenticator<C> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            iv: self.iv.clone(),
+        }
+    }
+}
+
+#[cfg(all(feature = "poly1305", feature = "zeroize"))]
+impl<C: KeyIvInit> Drop for Poly1305Authenticator<C> {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().zeroize();
+        self.iv.as_mut_slice().zeroize();
+    }
+}
+
+#[cfg(feature = "poly1305")]
+impl<C: KeyIvInit> Debug for Poly1305Authenticator<C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Poly1305Authenticator")
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`AddressableBuffer`] adapter verifying authentication tags of all pages
+/// touched by a read, before returning any data.
+///
+/// Data is split into pages of `page_size` bytes, last page could be
+/// shorter. Tag of page `n` is stored in separate `tags` buffer at position
+/// `n * tag_len`. Failed verification results in
+/// [`BufferError::IntegrityViolation`].
+#[derive(Clone, Debug)]
+pub struct AuthenticatedBuffer<B, T, A> {
+    data: B,
+    tags: T,
+    authenticator: A,
+    page_size: usize,
+    length: usize,
+}
+
+impl<B, T, A> AuthenticatedBuffer<B, T, A> {
+    /// New authenticated buffer.
+    ///
+    /// `tags` buffer must hold tags for all pages of `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if page size is zero.
+    pub fn new<E>(
+        data: B,
+        tags: T,
+        authenticator: A,
+        page_size: usize,
+    ) -> Result<Self, BufferError<E>>
+    where
+        B: AddressableBuffer<E>,
+        T: AddressableBuffer<E>,
+        A: PageAuthenticator,
+        E: ExternalMemory,
+    {
+        assert!(page_size != 0, "Page size must be non-zero.");
+        let length = data.total_len();
+        let tags_length = length.div_ceil(page_size) * authenticator.tag_len();
+        if tags.total_len() < tags_length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: tags_length,
+            });
+        }
+        Ok(Self {
+            data,
+            tags,
+            authenticator,
+            page_size,
+            length,
+        })
+    }
+
+    /// Page size.
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+}
+
+impl<B, T, A, E> AddressableBuffer<E> for AuthenticatedBuffer<B, T, A>
+where
+    B: AddressableBuffer<E>,
+    T: AddressableBuffer<E>,
+    A: PageAuthenticator + Clone,
+    E: ExternalMemory,
+{
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        check_span(self.length, position, dst.len())?;
+        if dst.is_empty() {
+            return Ok(());
+        }
+        // Full page is authenticated even if `limit_length` was applied.
+        let data_length = self.data.total_len();
+        let tag_len = self.authenticator.tag_len();
+        let mut page_data = vec![0; self.page_size];
+        let mut tag = vec![0; tag_len];
+        let first_page = position / self.page_size;
+        let last_page = (position + dst.len() - 1) / self.page_size;
+        for page in first_page..=last_page {
+            let page_start = page * self.page_size;
+            let page_len = self.page_size.min(data_length - page_start);
+            let page_data = &mut page_data[..page_len];
+            self.data.read_into(ext_memory, page_start, page_data)?;
+            self.tags.read_into(ext_memory, page * tag_len, &mut tag)?;
+            if !self.authenticator.verify(page, page_data, &tag) {
+                return Err(BufferError::IntegrityViolation { page });
+            }
+            let copy_start = position.max(page_start);
+            let copy_end = (position + dst.len()).min(page_start + page_len);
+            dst[copy_start - position..copy_end - position]
+                .copy_from_slice(&page_data[copy_start - page_start..copy_end - page_start]);
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            data: self.data.limit_length(self.data.total_len())?,
+            tags: self.tags.limit_length(self.tags.total_len())?,
+            authenticator: self.authenticator.clone(),
+            page_size: self.page_size,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "poly1305")]
+    use chacha20::ChaCha20;
+    use hmac::Hmac;
+    use sha2::Sha256;
+
+    use super::*;
+
+    const PAGE: usize = 8;
+
+    type HmacSha256 = Hmac<Sha256>;
+
+    fn authenticator() -> HmacSha256 {
+        <HmacSha256 as Mac>::new_from_slice(b"page key").unwrap()
+    }
+
+    /// 20 bytes of data, last page is short.
+    fn data() -> Vec<u8> {
+        (0..20).collect()
+    }
+
+    fn read(
+        data: &[u8],
+        tags: &[u8],
+        position: usize,
+        len: usize,
+    ) -> Result<Vec<u8>, BufferError<()>> {
+        AuthenticatedBuffer::new(data, tags, authenticator(), PAGE)?.read_slice(
+            &mut (),
+            position,
+            len,
+        )
+    }
+
+    #[test]
+    fn seal_covers_all_pages() {
+        let data = data();
+        let tags = authenticator().seal(&data, PAGE);
+        assert_eq!(tags.len(), 3 * 32);
+        let mut tag = [0; 32];
+        authenticator().compute(2, &data[16..], &mut tag);
+        assert_eq!(tags[64..], tag);
+        assert!(PageAuthenticator::verify(
+            &authenticator(),
+            2,
+            &data[16..],
+            &tag
+        ));
+        assert!(!PageAuthenticator::verify(
+            &authenticator(),
+            1,
+            &data[16..],
+            &tag
+        ));
+    }
+
+    #[test]
+    fn verified_reads() {
+        let data = data();
+        let tags = authenticator().seal(&data, PAGE);
+        assert_eq!(read(&data, &tags, 2, 3), Ok(vec![2, 3, 4]));
+        // Read spanning all pages, including the short last one.
+        assert_eq!(read(&data, &tags, 6, 14), Ok(data[6..].to_vec()));
+        assert_eq!(read(&data, &tags, 19, 1), Ok(vec![19]));
+        assert_eq!(read(&data, &tags, 20, 0), Ok(vec![]));
+        assert_eq!(
+            read(&data, &tags, 18, 3),
+            Err(BufferError::DataTooShort {
+                position: 18,
+                minimal_length: 3
+            })
+        );
+    }
+
+    #[test]
+    fn short_tags() {
+        let data = data();
+        let tags = authenticator().seal(&data, PAGE);
+        assert_eq!(
+            read(&data, &tags[..95], 0, 1),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 96
+            })
+        );
+    }
+
+    #[test]
+    fn flipped_data_byte() {
+        let mut data = data();
+        let tags = authenticator().seal(&data, PAGE);
+        data[17] ^= 1;
+        assert_eq!(read(&data, &tags, 0, 16), Ok(data[..16].to_vec()));
+        assert_eq!(
+            read(&data, &tags, 12, 6),
+            Err(BufferError::IntegrityViolation { page: 2 })
+        );
+        // Whole page is checked, even if the changed byte is not read.
+        assert_eq!(
+            read(&data, &tags, 19, 1),
+            Err(BufferError::IntegrityViolation { page: 2 })
+        );
+    }
+
+    #[test]
+    fn flipped_tag_byte() {
+        let data = data();
+        let mut tags = authenticator().seal(&data, PAGE);
+        tags[32 + 31] ^= 0x80;
+        assert_eq!(read(&data, &tags, 0, 8), Ok(data[..8].to_vec()));
+        assert_eq!(
+            read(&data, &tags, 0, 20),
+            Err(BufferError::IntegrityViolation { page: 1 })
+        );
+    }
+
+    #[test]
+    fn swapped_pages() {
+        let mut data = data();
+        let mut tags = authenticator().seal(&data, PAGE);
+        // Both data and tags of pages 0 and 1 are swapped.
+        let (first, second) = data.split_at_mut(PAGE);
+        first.swap_with_slice(&mut second[..PAGE]);
+        let (first, second) = tags.split_at_mut(32);
+        first.swap_with_slice(&mut second[..32]);
+        assert_eq!(
+            read(&data, &tags, 0, 1),
+            Err(BufferError::IntegrityViolation { page: 0 })
+        );
+        assert_eq!(
+            read(&data, &tags, 8, 1),
+            Err(BufferError::IntegrityViolation { page: 1 })
+        );
+        assert_eq!(read(&data, &tags, 16, 4), Ok(vec![16, 17, 18, 19]));
+    }
+
+    #[test]
+    fn reads_after_limit_length() {
+        let data = data();
+        let tags = authenticator().seal(&data, PAGE);
+        let buffer =
+            AuthenticatedBuffer::new::<()>(data.as_slice(), tags.as_slice(), authenticator(), PAGE)
+                .unwrap();
+        let limited = AddressableBuffer::<()>::limit_length(&buffer, 10).unwrap();
+        assert_eq!(AddressableBuffer::<()>::total_len(&limited), 10);
+        // Page 1 is still verified in full.
+        assert_eq!(limited.read_slice(&mut (), 6, 4), Ok(vec![6, 7, 8, 9]));
+        assert_eq!(
+            limited.read_slice(&mut (), 6, 5),
+            Err(BufferError::DataTooShort {
+                position: 6,
+                minimal_length: 5
+            })
+        );
+        assert!(AddressableBuffer::<()>::limit_length(&buffer, 21).is_err());
+    }
+
+    #[cfg(feature = "poly1305")]
+    fn poly1305(nonce: u8) -> Poly1305Authenticator<ChaCha20> {
+        Poly1305Authenticator::new(&[0x17; 32].into(), &[nonce; 12].into())
+    }
+
+    #[cfg(feature = "poly1305")]
+    #[test]
+    fn poly1305_pages() {
+        let data = data();
+        let authenticator = poly1305(1);
+        let tags = authenticator.seal(&data, PAGE);
+        assert_eq!(tags.len(), 3 * 16);
+        let buffer = AuthenticatedBuffer::new::<()>(
+            data.as_slice(),
+            tags.as_slice(),
+            authenticator.clone(),
+            PAGE,
+        )
+        .unwrap();
+        assert_eq!(buffer.read_slice(&mut (), 6, 14), Ok(data[6..].to_vec()));
+        // Keys differ between pages and between nonces.
+        assert!(authenticator.verify(0, &data[..8], &tags[..16]));
+        assert!(!authenticator.verify(1, &data[..8], &tags[..16]));
+        assert!(!poly1305(2).verify(0, &data[..8], &tags[..16]));
+        assert!(!authenticator.verify(0, &data[..8], &tags[..15]));
+        assert_eq!(format!("{authenticator:?}"), "Poly1305Authenticator { .. }");
+    }
+
+    #[cfg(feature = "poly1305")]
+    #[test]
+    fn poly1305_tampering() {
+        let mut data = data();
+        let authenticator = poly1305(1);
+        let mut tags = authenticator.seal(&data, PAGE);
+        data[17] ^= 1;
+        tags[15] ^= 1;
+        let buffer =
+            AuthenticatedBuffer::new::<()>(data.as_slice(), tags.as_slice(), authenticator, PAGE)
+                .unwrap();
+        assert_eq!(buffer.read_slice(&mut (), 8, 8), Ok(data[8..16].to_vec()));
+        assert_eq!(
+            buffer.read_slice(&mut (), 0, 1),
+            Err(BufferError::IntegrityViolation { page: 0 })
+        );
+        assert_eq!(
+            buffer.read_slice(&mut (), 16, 1),
+            Err(BufferError::IntegrityViolation { page: 2 })
+        );
+    }
+
+    #[cfg(feature = "poly1305")]
+    #[test]
+    fn poly1305_swapped_pages() {
+        let mut data = data();
+        let authenticator = poly1305(1);
+        let mut tags = authenticator.seal(&data, PAGE);
+        // Both data and tags of pages 0 and 1 are swapped.
+        let (first, second) = data.split_at_mut(PAGE);
+        first.swap_with_slice(&mut second[..PAGE]);
+        let (first, second) = tags.split_at_mut(16);
+        first.swap_with_slice(&mut second[..16]);
+        let buffer =
+            AuthenticatedBuffer::new::<()>(data.as_slice(), tags.as_slice(), authenticator, PAGE)
+                .unwrap();
+        assert_eq!(
+            buffer.read_slice(&mut (), 8, 1),
+            Err(BufferError::IntegrityViolation { page: 1 })
+        );
+    }
+}
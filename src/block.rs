@@ -0,0 +1,331 @@
+//! Block devices, such as SD/MMC cards, as external memory.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    ops::Range,
+};
+
+#[cfg(feature = "write")]
+use crate::WritableBuffer;
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Block size of SD/MMC cards.
+pub const BLOCK_SIZE: usize = 512;
+
+/// Single device block.
+pub type Block = [u8; BLOCK_SIZE];
+
+/// Device accessed only in whole blocks of [`BLOCK_SIZE`] bytes.
+pub trait BlockDevice: Debug {
+    /// Errors specific to device accessing.
+    type Error: Debug + Display + Eq + PartialEq;
+
+    /// Total number of blocks on device.
+    fn block_count(&self) -> u32;
+
+    /// Read consecutive blocks starting from known block index.
+    fn read_blocks(&mut self, start_block: u32, blocks: &mut [Block]) -> Result<(), Self::Error>;
+
+    /// Write consecutive blocks starting from known block index.
+    fn write_blocks(&mut self, start_block: u32, blocks: &[Block]) -> Result<(), Self::Error>;
+}
+
+/// [`BlockDevice`] used as [`ExternalMemory`].
+///
+/// Arbitrary byte ranges are translated into aligned block accesses through
+/// a one-block scratch buffer. Regions of device are [`BlockRegion`] values.
+#[derive(Debug)]
+pub struct BlockMemory<D> {
+    device: D,
+    scratch: Block,
+}
+
+impl<D: BlockDevice> BlockMemory<D> {
+    /// Wrap device.
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            scratch: [0; BLOCK_SIZE],
+        }
+    }
+
+    /// Wrapped device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Release wrapped device.
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
+    /// Total device capacity in bytes.
+    pub fn capacity(&self) -> u64 {
+        self.device.block_count() as u64 * BLOCK_SIZE as u64
+    }
+
+    /// Region of device, checked against device capacity.
+    pub fn region(&self, offset: u64, length: usize) -> Result<BlockRegion, BufferError<Self>> {
+        let fits = offset
+            .checked_add(length as u64)
+            .is_some_and(|end| end <= self.capacity());
+        if !fits {
+            return Err(BufferError::DataTooShort {
+                position: offset as usize,
+                minimal_length: length,
+            });
+        }
+        Ok(BlockRegion { offset, length })
+    }
+}
+
+impl<D: BlockDevice> ExternalMemory for BlockMemory<D> {
+    type ExternalMemoryError = D::Error;
+}
+
+/// Region of [`BlockMemory`]: byte offset on device and region length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockRegion {
+    offset: u64,
+    length: usize,
+}
+
+impl BlockRegion {
+    /// Block index and offset within block for position in region.
+    fn locate(&self, position: usize) -> (u32, usize) {
+        let address = self.offset + position as u64;
+        (
+            (address / BLOCK_SIZE as u64) as u32,
+            (address % BLOCK_SIZE as u64) as usize,
+        )
+    }
+}
+
+impl<D: BlockDevice> AddressableBuffer<BlockMemory<D>> for BlockRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut BlockMemory<D>,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<BlockMemory<D>>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut BlockMemory<D>,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<BlockMemory<D>>> {
+        check_span(self.length, position, dst.len())?;
+        let mut done = 0;
+        while done < dst.len() {
+            let (block, block_offset) = self.locate(position + done);
+            let copy_len = (BLOCK_SIZE - block_offset).min(dst.len() - done);
+            ext_memory
+                .device
+                .read_blocks(block, core::slice::from_mut(&mut ext_memory.scratch))
+                .map_err(BufferError::External)?;
+            dst[done..done + copy_len]
+                .copy_from_slice(&ext_memory.scratch[block_offset..block_offset + copy_len]);
+            done += copy_len;
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<BlockMemory<D>>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(feature = "write")]
+impl<D: BlockDevice> WritableBuffer<BlockMemory<D>> for BlockRegion {
+    /// Partially covered blocks are read, modified and written back.
+    fn write_slice(
+        &mut self,
+        ext_memory: &mut BlockMemory<D>,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<BlockMemory<D>>> {
+        if self.length < position || self.length - position < data.len() {
+            return Err(BufferError::WritePastEnd {
+                position,
+                write_length: data.len(),
+                total_length: self.length,
+            });
+        }
+        let mut done = 0;
+        while done < data.len() {
+            let (block, block_offset) = self.locate(position + done);
+            let copy_len = (BLOCK_SIZE - block_offset).min(data.len() - done);
+            let scratch = core::slice::from_mut(&mut ext_memory.scratch);
+            if copy_len != BLOCK_SIZE {
+                ext_memory
+                    .device
+                    .read_blocks(block, scratch)
+                    .map_err(BufferError::External)?;
+            }
+            scratch[0][block_offset..block_offset + copy_len]
+                .copy_from_slice(&data[done..done + copy_len]);
+            ext_memory
+                .device
+                .write_blocks(block, scratch)
+                .map_err(BufferError::External)?;
+            done += copy_len;
+        }
+        Ok(())
+    }
+}
+
+/// In-memory [`BlockDevice`], for testing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RamBlockDevice {
+    blocks: Vec<Block>,
+}
+
+impl RamBlockDevice {
+    /// New device with known number of zeroed blocks.
+    pub fn new(block_count: u32) -> Self {
+        Self {
+            blocks: vec![[0; BLOCK_SIZE]; block_count as usize],
+        }
+    }
+
+    /// Device blocks.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    fn range(&self, start_block: u32, count: usize) -> Result<Range<usize>, RamBlockError> {
+        let start = start_block as usize;
+        match start.checked_add(count) {
+            Some(end) if end <= self.blocks.len() => Ok(start..end),
+            _ => Err(RamBlockError::OutOfRange {
+                start_block,
+                block_count: self.blocks.len() as u32,
+            }),
+        }
+    }
+}
+
+impl BlockDevice for RamBlockDevice {
+    type Error = RamBlockError;
+    fn block_count(&self) -> u32 {
+        self.blocks.len() as u32
+    }
+    fn read_blocks(&mut self, start_block: u32, blocks: &mut [Block]) -> Result<(), Self::Error> {
+        let range = self.range(start_block, blocks.len())?;
+        blocks.copy_from_slice(&self.blocks[range]);
+        Ok(())
+    }
+    fn write_blocks(&mut self, start_block: u32, blocks: &[Block]) -> Result<(), Self::Error> {
+        let range = self.range(start_block, blocks.len())?;
+        self.blocks[range].copy_from_slice(blocks);
+        Ok(())
+    }
+}
+
+/// Error accessing [`RamBlockDevice`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RamBlockError {
+    OutOfRange { start_block: u32, block_count: u32 },
+}
+
+impl Display for RamBlockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            RamBlockError::OutOfRange {
+                start_block,
+                block_count,
+            } => write!(
+                f,
+                "Block access starting at {start_block} is out of range for device with {block_count} block(s)."
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn region_checks() {
+        let memory = BlockMemory::new(RamBlockDevice::new(2));
+        assert!(memory.region(100, 924).is_ok());
+        assert!(matches!(
+            memory.region(100, 925),
+            Err(BufferError::DataTooShort {
+                position: 100,
+                minimal_length: 925
+            })
+        ));
+    }
+
+    #[test]
+    fn read_across_blocks() {
+        let mut device = RamBlockDevice::new(3);
+        let blocks: Vec<Block> = (0..3u8).map(|a| [a + 1; BLOCK_SIZE]).collect();
+        device.write_blocks(0, &blocks).unwrap();
+        let mut memory = BlockMemory::new(device);
+        let region = memory.region(500, 1000).unwrap();
+        let data = region.read_slice(&mut memory, 10, 520).unwrap();
+        assert_eq!(data[..2], [1, 1]);
+        assert!(data[2..514].iter().all(|a| *a == 2));
+        assert!(data[514..].iter().all(|a| *a == 3));
+        assert!(matches!(
+            region.read_slice(&mut memory, 990, 11),
+            Err(BufferError::DataTooShort {
+                position: 990,
+                minimal_length: 11
+            })
+        ));
+    }
+
+    #[test]
+    fn device_out_of_range() {
+        let mut device = RamBlockDevice::new(2);
+        assert_eq!(
+            device.read_blocks(1, &mut [[0; BLOCK_SIZE]; 2]),
+            Err(RamBlockError::OutOfRange {
+                start_block: 1,
+                block_count: 2
+            })
+        );
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn write_keeps_neighbors() {
+        let mut memory = BlockMemory::new(RamBlockDevice::new(3));
+        let mut region = memory.region(0, 3 * BLOCK_SIZE).unwrap();
+        region
+            .write_slice(&mut memory, 0, &[0xaa; 3 * BLOCK_SIZE])
+            .unwrap();
+        region.write_slice(&mut memory, 510, &[1; 516]).unwrap();
+        let blocks = memory.device().blocks();
+        assert!(blocks[0][..510].iter().all(|a| *a == 0xaa));
+        assert!(blocks[0][510..].iter().all(|a| *a == 1));
+        assert!(blocks[1].iter().all(|a| *a == 1));
+        assert_eq!(blocks[2][..2], [1, 1]);
+        assert!(blocks[2][2..].iter().all(|a| *a == 0xaa));
+    }
+}
@@ -0,0 +1,346 @@
+//! Page cache for expensive external memory reads.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::{cell::RefCell, marker::PhantomData};
+
+use crate::{
+    check_span, AddressableBuffer, BufferError, Debug, ExternalMemory, FmtResult, Formatter,
+};
+
+/// Cache usage statistics.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// Number of page accesses served from cache.
+    pub hits: u64,
+    /// Number of page accesses that required reading from inner buffer.
+    pub misses: u64,
+}
+
+/// [`AddressableBuffer`] adapter keeping up to `PAGES` recently used pages of
+/// `PAGE` bytes in RAM.
+///
+/// Reads are done from the inner buffer only in whole pages, least recently
+/// used page is evicted when the cache is full. Cache is stored inline, no
+/// allocations are needed for `read_into`, `read_array` and `read_byte`.
+pub struct CachedBuffer<B, E, const PAGE: usize, const PAGES: usize> {
+    inner: B,
+    cache: RefCell<PageCache<PAGE, PAGES>>,
+    ext_memory_type: PhantomData<E>,
+}
+
+struct PageCache<const PAGE: usize, const PAGES: usize> {
+    pages: [[u8; PAGE]; PAGES],
+    page_indices: [Option<usize>; PAGES],
+    last_used: [u64; PAGES],
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl<const PAGE: usize, const PAGES: usize> PageCache<PAGE, PAGES> {
+    fn new() -> Self {
+        Self {
+            pages: [[0; PAGE]; PAGES],
+            page_indices: [None; PAGES],
+            last_used: [0; PAGES],
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.page_indices = [None; PAGES];
+    }
+}
+
+impl<B, E, const PAGE: usize, const PAGES: usize> CachedBuffer<B, E, PAGE, PAGES>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    /// New cache over inner buffer, initially empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `PAGE` or `PAGES` is zero.
+    pub fn new(inner: B) -> Self {
+        assert!(
+            PAGE != 0 && PAGES != 0,
+            "Page size and number of pages must be non-zero."
+        );
+        Self {
+            inner,
+            cache: RefCell::new(PageCache::new()),
+            ext_memory_type: PhantomData,
+        }
+    }
+
+    /// Inner buffer.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Release inner buffer, dropping the cache.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+
+    /// Cache usage statistics.
+    pub fn stats(&self) -> CacheStats {
+        self.cache.borrow().stats
+    }
+
+    /// Reset cache usage statistics.
+    pub fn reset_stats(&self) {
+        self.cache.borrow_mut().stats = CacheStats::default();
+    }
+
+    /// Drop all cached pages, for example if the memory was changed.
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().invalidate();
+    }
+}
+
+impl<B, E, const PAGE: usize, const PAGES: usize> AddressableBuffer<E>
+    for CachedBuffer<B, E, PAGE, PAGES>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.inner.total_len()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        let total_length = self.total_len();
+        check_span(total_length, position, dst.len())?;
+        let mut cache = self.cache.borrow_mut();
+        let mut done = 0;
+        while done < dst.len() {
+            let current = position + done;
+            let page_index = current / PAGE;
+            let offset = current % PAGE;
+            cache.clock += 1;
+            let slot = match cache
+                .page_indices
+                .iter()
+                .position(|a| *a == Some(page_index))
+            {
+                Some(slot) => {
+                    cache.stats.hits += 1;
+                    slot
+                }
+                None => {
+                    cache.stats.misses += 1;
+                    let slot = match cache.page_indices.iter().position(|a| a.is_none()) {
+                        Some(empty) => empty,
+                        None => (0..PAGES)
+                            .min_by_key(|slot| cache.last_used[*slot])
+                            .expect("number of pages is non-zero"),
+                    };
+                    let page_start = page_index * PAGE;
+                    let page_len = PAGE.min(total_length - page_start);
+                    cache.page_indices[slot] = None;
+                    self.inner.read_into(
+                        ext_memory,
+                        page_start,
+                        &mut cache.pages[slot][..page_len],
+                    )?;
+                    cache.page_indices[slot] = Some(page_index);
+                    slot
+                }
+            };
+            cache.last_used[slot] = cache.clock;
+            let copy_len = (PAGE - offset).min(dst.len() - done);
+            dst[done..done + copy_len]
+                .copy_from_slice(&cache.pages[slot][offset..offset + copy_len]);
+            done += copy_len;
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        Ok(Self::new(self.inner.limit_length(new_len)?))
+    }
+}
+
+impl<B: Debug, E, const PAGE: usize, const PAGES: usize> Debug for CachedBuffer<B, E, PAGE, PAGES> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CachedBuffer")
+            .field("inner", &self.inner)
+            .field("page_size", &PAGE)
+            .field("pages", &PAGES)
+            .field("stats", &self.cache.borrow().stats)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Display;
+
+    use super::*;
+
+    const DATA: &[u8] = &[
+        0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+    ];
+
+    /// Memory recording reads of inner buffer, as page start and length.
+    #[derive(Debug, Default, Eq, PartialEq)]
+    struct CountingMemory {
+        reads: Vec<(usize, usize)>,
+        fail: bool,
+    }
+
+    impl ExternalMemory for CountingMemory {
+        type ExternalMemoryError = ReadFailed;
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct ReadFailed;
+
+    impl Display for ReadFailed {
+        fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+            write!(f, "Read failed.")
+        }
+    }
+
+    #[derive(Debug)]
+    struct CountingBuffer(&'static [u8]);
+
+    impl AddressableBuffer<CountingMemory> for CountingBuffer {
+        type ReadBuffer = &'static [u8];
+        fn total_len(&self) -> usize {
+            self.0.len()
+        }
+        fn read_slice(
+            &self,
+            ext_memory: &mut CountingMemory,
+            position: usize,
+            slice_len: usize,
+        ) -> Result<Self::ReadBuffer, BufferError<CountingMemory>> {
+            if ext_memory.fail {
+                return Err(BufferError::External(ReadFailed));
+            }
+            ext_memory.reads.push((position, slice_len));
+            Ok(&self.0[position..position + slice_len])
+        }
+        fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<CountingMemory>> {
+            Ok(Self(&self.0[..new_len]))
+        }
+    }
+
+    type Cache = CachedBuffer<CountingBuffer, CountingMemory, 4, 2>;
+
+    #[test]
+    fn hits_and_misses() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        assert_eq!(cache.read_slice(&mut memory, 1, 2), Ok(vec![1, 2]));
+        assert_eq!(cache.read_byte(&mut memory, 3), Ok(3));
+        assert_eq!(memory.reads, [(0, 4)]);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+        cache.reset_stats();
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn read_spanning_pages() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        assert_eq!(
+            cache.read_slice(&mut memory, 3, 6),
+            Ok(vec![3, 4, 5, 6, 7, 8])
+        );
+        assert_eq!(memory.reads, [(0, 4), (4, 4), (8, 4)]);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 3 });
+    }
+
+    #[test]
+    fn least_recently_used_evicted() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        cache.read_byte(&mut memory, 0).unwrap();
+        cache.read_byte(&mut memory, 4).unwrap();
+        // Page 0 becomes most recently used, page 1 is evicted for page 2.
+        cache.read_byte(&mut memory, 1).unwrap();
+        cache.read_byte(&mut memory, 8).unwrap();
+        cache.read_byte(&mut memory, 2).unwrap();
+        assert_eq!(memory.reads, [(0, 4), (4, 4), (8, 4)]);
+        cache.read_byte(&mut memory, 5).unwrap();
+        assert_eq!(memory.reads[3..], [(4, 4)]);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+    }
+
+    #[test]
+    fn short_last_page() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        assert_eq!(cache.read_slice(&mut memory, 19, 3), Ok(vec![19, 20, 21]));
+        assert_eq!(memory.reads, [(16, 4), (20, 2)]);
+        assert!(matches!(
+            cache.read_slice(&mut memory, 20, 3),
+            Err(BufferError::DataTooShort {
+                position: 20,
+                minimal_length: 3
+            })
+        ));
+        assert_eq!(memory.reads.len(), 2);
+    }
+
+    #[test]
+    fn recovery_after_inner_error() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        cache.read_byte(&mut memory, 0).unwrap();
+        memory.fail = true;
+        assert!(matches!(
+            cache.read_byte(&mut memory, 4),
+            Err(BufferError::External(ReadFailed))
+        ));
+        // Cached page is still served, failed page is not.
+        assert_eq!(cache.read_byte(&mut memory, 1), Ok(1));
+        memory.fail = false;
+        assert_eq!(cache.read_byte(&mut memory, 4), Ok(4));
+        assert_eq!(memory.reads, [(0, 4), (4, 4)]);
+    }
+
+    #[test]
+    fn invalidate_drops_pages() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        cache.read_byte(&mut memory, 0).unwrap();
+        cache.invalidate();
+        cache.read_byte(&mut memory, 0).unwrap();
+        assert_eq!(memory.reads, [(0, 4), (0, 4)]);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 2 });
+    }
+
+    #[test]
+    fn limit_length_starts_empty() {
+        let cache = Cache::new(CountingBuffer(DATA));
+        let mut memory = CountingMemory::default();
+        cache.read_byte(&mut memory, 0).unwrap();
+        let limited = cache.limit_length(6).unwrap();
+        assert_eq!(limited.total_len(), 6);
+        assert_eq!(limited.read_slice(&mut memory, 3, 3), Ok(vec![3, 4, 5]));
+        assert_eq!(memory.reads, [(0, 4), (0, 4), (4, 2)]);
+    }
+}
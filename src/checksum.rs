@@ -0,0 +1,294 @@
+//! Checksums and digests of buffer ranges, computed in bounded-size chunks.
+use core::ops::Range;
+
+use crate::{check_range, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Number of bytes read from buffer at once when processing ranges.
+pub const CHECKSUM_CHUNK: usize = 256;
+
+/// CRC-32 (IEEE 802.3) lookup table, reflected polynomial `0xEDB88320`.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-16/CCITT-FALSE lookup table, polynomial `0x1021`.
+const CRC16_TABLE: [u16; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 == 0x8000 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Streaming CRC-32 (IEEE 802.3, as in zip and Ethernet).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    /// New CRC-32 computation.
+    pub const fn new() -> Self {
+        Self { state: u32::MAX }
+    }
+
+    /// Process more data.
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state =
+                CRC32_TABLE[((self.state ^ *byte as u32) & 0xff) as usize] ^ (self.state >> 8);
+        }
+    }
+
+    /// Checksum of all processed data.
+    pub const fn finalize(&self) -> u32 {
+        !self.state
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streaming CRC-16/CCITT-FALSE.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Crc16 {
+    state: u16,
+}
+
+impl Crc16 {
+    /// New CRC-16 computation.
+    pub const fn new() -> Self {
+        Self { state: u16::MAX }
+    }
+
+    /// Process more data.
+    pub fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            self.state =
+                CRC16_TABLE[((self.state >> 8) as u8 ^ *byte) as usize] ^ (self.state << 8);
+        }
+    }
+
+    /// Checksum of all processed data.
+    pub const fn finalize(&self) -> u16 {
+        self.state
+    }
+}
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "digest")]
+impl digest::Update for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        Crc32::update(self, data)
+    }
+}
+
+#[cfg(feature = "digest")]
+impl digest::Update for Crc16 {
+    fn update(&mut self, data: &[u8]) {
+        Crc16::update(self, data)
+    }
+}
+
+/// Feed buffer range into `process` in chunks of at most [`CHECKSUM_CHUNK`]
+/// bytes, read into stack memory.
+pub(crate) fn for_each_chunk<B, E, F>(
+    buffer: &B,
+    ext_memory: &mut E,
+    range: Range<usize>,
+    mut process: F,
+) -> Result<(), BufferError<E>>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+    F: FnMut(&[u8]),
+{
+    check_range(buffer.total_len(), &range)?;
+    let mut chunk = [0; CHECKSUM_CHUNK];
+    let mut position = range.start;
+    while position < range.end {
+        let chunk_len = CHECKSUM_CHUNK.min(range.end - position);
+        buffer.read_into(ext_memory, position, &mut chunk[..chunk_len])?;
+        process(&chunk[..chunk_len]);
+        position += chunk_len;
+    }
+    Ok(())
+}
+
+/// CRC-32 (IEEE 802.3) of buffer range.
+pub fn crc32_range<B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    range: Range<usize>,
+) -> Result<u32, BufferError<E>>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    let mut crc = Crc32::new();
+    for_each_chunk(buffer, ext_memory, range, |chunk| crc.update(chunk))?;
+    Ok(crc.finalize())
+}
+
+/// CRC-16/CCITT-FALSE of buffer range.
+pub fn crc16_range<B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    range: Range<usize>,
+) -> Result<u16, BufferError<E>>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    let mut crc = Crc16::new();
+    for_each_chunk(buffer, ext_memory, range, |chunk| crc.update(chunk))?;
+    Ok(crc.finalize())
+}
+
+/// Feed buffer range into digest, for example hash or MAC, without copying
+/// the whole range into RAM.
+#[cfg(feature = "digest")]
+pub fn digest_range<B, E, D>(
+    buffer: &B,
+    ext_memory: &mut E,
+    range: Range<usize>,
+    digest: &mut D,
+) -> Result<(), BufferError<E>>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+    D: digest::Update,
+{
+    for_each_chunk(buffer, ext_memory, range, |chunk| digest.update(chunk))
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+
+    use super::*;
+
+    const CHECK: &[u8] = b"123456789";
+
+    #[test]
+    fn known_vectors() {
+        let mut crc32 = Crc32::new();
+        crc32.update(CHECK);
+        assert_eq!(crc32.finalize(), 0xCBF4_3926);
+        let mut crc16 = Crc16::new();
+        crc16.update(CHECK);
+        assert_eq!(crc16.finalize(), 0x29B1);
+        assert_eq!(Crc32::new().finalize(), 0);
+        assert_eq!(Crc16::new().finalize(), 0xFFFF);
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let mut crc32 = Crc32::new();
+        let mut crc16 = Crc16::new();
+        for part in CHECK.chunks(2) {
+            crc32.update(part);
+            crc16.update(part);
+        }
+        assert_eq!(crc32.finalize(), 0xCBF4_3926);
+        assert_eq!(crc16.finalize(), 0x29B1);
+    }
+
+    #[test]
+    fn ranges_span_chunks() {
+        let data: Vec<u8> = (0..3 * CHECKSUM_CHUNK + 5).map(|a| a as u8).collect();
+        for range in [0..0, 3..12, 0..data.len(), 100..2 * CHECKSUM_CHUNK + 1] {
+            let mut crc32 = Crc32::new();
+            crc32.update(&data[range.clone()]);
+            let mut crc16 = Crc16::new();
+            crc16.update(&data[range.clone()]);
+            let buffer = data.as_slice();
+            assert_eq!(
+                crc32_range(&buffer, &mut (), range.clone()),
+                Ok(crc32.finalize())
+            );
+            assert_eq!(crc16_range(&buffer, &mut (), range), Ok(crc16.finalize()));
+        }
+        let mut padded = b"xx".to_vec();
+        padded.extend_from_slice(CHECK);
+        assert_eq!(
+            crc32_range(&padded.as_slice(), &mut (), 2..11),
+            Ok(0xCBF4_3926)
+        );
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn range_errors() {
+        let buffer = CHECK;
+        assert_eq!(
+            crc32_range(&buffer, &mut (), 5..10),
+            Err(BufferError::DataTooShort {
+                position: 5,
+                minimal_length: 5
+            })
+        );
+        assert_eq!(
+            crc16_range(&buffer, &mut (), 10..12),
+            Err(BufferError::OutOfRange {
+                position: 10,
+                total_length: 9
+            })
+        );
+        assert_eq!(
+            crc16_range(&buffer, &mut (), 4..2),
+            Err(BufferError::InvalidRange { start: 4, end: 2 })
+        );
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn digest_matches_direct_hash() {
+        use sha2::{Digest, Sha256};
+
+        let data: Vec<u8> = (0..1000).map(|a| (a % 251) as u8).collect();
+        let mut hasher = Sha256::new();
+        digest_range(&data.as_slice(), &mut (), 7..900, &mut hasher).unwrap();
+        assert_eq!(hasher.finalize(), Sha256::digest(&data[7..900]));
+    }
+}
@@ -0,0 +1,228 @@
+//! Constant-time comparison of buffer contents.
+use core::{hint::black_box, ops::Range};
+
+use crate::{
+    check_range, checksum::for_each_chunk, AddressableBuffer, BufferError, ExternalMemory,
+    CHECKSUM_CHUNK,
+};
+
+/// Compare buffer range with data in RAM, in time independent of contents.
+///
+/// Range is read in chunks of at most [`CHECKSUM_CHUNK`] bytes and every
+/// byte is compared regardless of earlier mismatches. Lengths are not
+/// secret: range of different length is unequal without reading the buffer.
+pub fn ct_eq_slice<B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    range: Range<usize>,
+    expected: &[u8],
+) -> Result<bool, BufferError<E>>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    check_range(buffer.total_len(), &range)?;
+    if range.len() != expected.len() {
+        return Ok(false);
+    }
+    let mut difference = 0u8;
+    let mut offset = 0;
+    for_each_chunk(buffer, ext_memory, range, |chunk| {
+        difference = accumulate(difference, chunk, &expected[offset..offset + chunk.len()]);
+        offset += chunk.len();
+    })?;
+    Ok(black_box(difference) == 0)
+}
+
+/// Compare ranges of two buffers in the same external memory, in time
+/// independent of contents.
+///
+/// Ranges are read in chunks of at most [`CHECKSUM_CHUNK`] bytes. Lengths
+/// are not secret: ranges of different length are unequal without reading
+/// the buffers.
+pub fn ct_eq_buffers<B1, B2, E>(
+    first: &B1,
+    first_range: Range<usize>,
+    second: &B2,
+    second_range: Range<usize>,
+    ext_memory: &mut E,
+) -> Result<bool, BufferError<E>>
+where
+    B1: AddressableBuffer<E>,
+    B2: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    check_range(first.total_len(), &first_range)?;
+    check_range(second.total_len(), &second_range)?;
+    if first_range.len() != second_range.len() {
+        return Ok(false);
+    }
+    let mut first_chunk = [0; CHECKSUM_CHUNK];
+    let mut second_chunk = [0; CHECKSUM_CHUNK];
+    let mut difference = 0u8;
+    let mut offset = 0;
+    while offset < first_range.len() {
+        let chunk_len = CHECKSUM_CHUNK.min(first_range.len() - offset);
+        first.read_into(
+            ext_memory,
+            first_range.start + offset,
+            &mut first_chunk[..chunk_len],
+        )?;
+        second.read_into(
+            ext_memory,
+            second_range.start + offset,
+            &mut second_chunk[..chunk_len],
+        )?;
+        difference = accumulate(
+            difference,
+            &first_chunk[..chunk_len],
+            &second_chunk[..chunk_len],
+        );
+        offset += chunk_len;
+    }
+    Ok(black_box(difference) == 0)
+}
+
+/// Accumulate differing bits of equal-length slices, without branching on
+/// contents.
+pub(crate) fn accumulate(mut difference: u8, first: &[u8], second: &[u8]) -> u8 {
+    for (a, b) in first.iter().zip(second) {
+        difference = black_box(difference | (a ^ b));
+    }
+    difference
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+
+    use super::*;
+
+    /// Data longer than two chunks.
+    fn data() -> Vec<u8> {
+        (0..600).map(|a| (a * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn slice_equal_and_unequal() {
+        let data = data();
+        let buffer = data.as_slice();
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 0..600, &data), Ok(true));
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 5..9, &data[5..9]), Ok(true));
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 5..9, &data[6..10]), Ok(false));
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 3..3, &[]), Ok(true));
+    }
+
+    #[test]
+    fn slice_mismatch_in_last_byte_of_chunk() {
+        let data = data();
+        let buffer = data.as_slice();
+        for position in [CHECKSUM_CHUNK * 2 - 1, 599] {
+            let mut expected = data.clone();
+            expected[position] ^= 1;
+            assert_eq!(ct_eq_slice(&buffer, &mut (), 0..600, &expected), Ok(false));
+        }
+    }
+
+    #[test]
+    fn slice_of_different_length() {
+        let data = data();
+        let buffer = data.as_slice();
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 0..4, &data[..5]), Ok(false));
+        assert_eq!(ct_eq_slice(&buffer, &mut (), 0..5, &data[..4]), Ok(false));
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn slice_invalid_ranges() {
+        let data = data();
+        let buffer = data.as_slice();
+        assert_eq!(
+            ct_eq_slice(&buffer, &mut (), 590..610, &[0; 20]),
+            Err(BufferError::DataTooShort {
+                position: 590,
+                minimal_length: 20
+            })
+        );
+        assert_eq!(
+            ct_eq_slice(&buffer, &mut (), 601..601, &[]),
+            Err(BufferError::OutOfRange {
+                position: 601,
+                total_length: 600
+            })
+        );
+        assert_eq!(
+            ct_eq_slice(&buffer, &mut (), 9..5, &[]),
+            Err(BufferError::InvalidRange { start: 9, end: 5 })
+        );
+    }
+
+    #[test]
+    fn buffers_equal_and_unequal() {
+        let data = data();
+        let first = data.as_slice();
+        let mut copy = vec![0; 20];
+        copy.extend_from_slice(&data);
+        let second = copy.as_slice();
+        assert_eq!(
+            ct_eq_buffers(&first, 0..600, &second, 20..620, &mut ()),
+            Ok(true)
+        );
+        assert_eq!(
+            ct_eq_buffers(&first, 10..20, &second, 10..20, &mut ()),
+            Ok(false)
+        );
+        copy[20 + CHECKSUM_CHUNK * 2 - 1] ^= 0x80;
+        let second = copy.as_slice();
+        assert_eq!(
+            ct_eq_buffers(&first, 0..600, &second, 20..620, &mut ()),
+            Ok(false)
+        );
+        assert_eq!(
+            ct_eq_buffers(
+                &first,
+                0..CHECKSUM_CHUNK,
+                &second,
+                20..CHECKSUM_CHUNK + 20,
+                &mut ()
+            ),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn buffers_invalid_ranges() {
+        let data = data();
+        let buffer = data.as_slice();
+        assert_eq!(
+            ct_eq_buffers(&buffer, 0..4, &buffer, 0..5, &mut ()),
+            Ok(false)
+        );
+        assert_eq!(
+            ct_eq_buffers(&buffer, 0..4, &buffer, 598..602, &mut ()),
+            Err(BufferError::DataTooShort {
+                position: 598,
+                minimal_length: 4
+            })
+        );
+        assert_eq!(
+            ct_eq_buffers(&buffer, 4..0, &buffer, 0..4, &mut ()),
+            Err(BufferError::InvalidRange { start: 4, end: 0 })
+        );
+    }
+
+    #[test]
+    fn accumulate_differences() {
+        assert_eq!(accumulate(0, &[], &[]), 0);
+        assert_eq!(accumulate(0, &[1, 2, 3], &[1, 2, 3]), 0);
+        assert_eq!(accumulate(0, &[1, 2, 3], &[1, 2, 7]), 4);
+        assert_eq!(accumulate(0, &[0x0f, 0], &[0, 0xf0]), 0xff);
+        // Earlier difference is kept.
+        assert_eq!(accumulate(0x10, &[1], &[1]), 0x10);
+    }
+}
@@ -0,0 +1,220 @@
+//! Several buffers addressed as one.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Ordered buffers presented as single contiguous [`AddressableBuffer`].
+///
+/// Useful when data is split across non-contiguous memory regions. Reads
+/// could straddle the boundaries between the parts; errors are reported with
+/// positions in the combined buffer.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Concat<B> {
+    parts: Vec<B>,
+}
+
+impl<B> Concat<B> {
+    /// New combined buffer from ordered parts.
+    pub fn new(parts: Vec<B>) -> Self {
+        Self { parts }
+    }
+
+    /// Add part to the end of combined buffer.
+    pub fn push(&mut self, part: B) {
+        self.parts.push(part)
+    }
+
+    /// Buffer parts.
+    pub fn parts(&self) -> &[B] {
+        &self.parts
+    }
+
+    /// Release buffer parts.
+    pub fn into_parts(self) -> Vec<B> {
+        self.parts
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> AddressableBuffer<E> for Concat<B> {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.parts.iter().map(|part| part.total_len()).sum()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        let total_length = self.total_len();
+        check_span(total_length, position, dst.len())?;
+        let mut part_start = 0;
+        let mut done = 0;
+        for part in self.parts.iter() {
+            if done == dst.len() {
+                break;
+            }
+            let part_len = part.total_len();
+            let current = position + done;
+            if current < part_start + part_len {
+                let local_position = current - part_start;
+                let copy_len = (part_len - local_position).min(dst.len() - done);
+                part.read_into(ext_memory, local_position, &mut dst[done..done + copy_len])
+                    .map_err(|e| e.shift_position(part_start, total_length))?;
+                done += copy_len;
+            }
+            part_start += part_len;
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        let mut parts = Vec::new();
+        let mut remaining = new_len;
+        for part in self.parts.iter() {
+            if remaining == 0 {
+                break;
+            }
+            let part_len = part.total_len().min(remaining);
+            parts.push(part.limit_length(part_len)?);
+            remaining -= part_len;
+        }
+        if remaining != 0 {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts() -> Concat<&'static [u8]> {
+        Concat::new(vec![&[0, 1, 2][..], &[], &[3, 4], &[5, 6, 7, 8]])
+    }
+
+    /// Part claiming to be longer than the data it actually has.
+    #[derive(Clone, Debug)]
+    struct Truncated {
+        data: &'static [u8],
+        claimed: usize,
+    }
+
+    impl AddressableBuffer<()> for Truncated {
+        type ReadBuffer = &'static [u8];
+        fn total_len(&self) -> usize {
+            self.claimed
+        }
+        fn read_slice(
+            &self,
+            ext_memory: &mut (),
+            position: usize,
+            slice_len: usize,
+        ) -> Result<Self::ReadBuffer, BufferError<()>> {
+            self.data.read_slice(ext_memory, position, slice_len)
+        }
+        fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<()>> {
+            Ok(Self {
+                data: &self.data[..new_len.min(self.data.len())],
+                claimed: new_len,
+            })
+        }
+    }
+
+    #[test]
+    fn reads_straddle_parts() {
+        let concat = parts();
+        assert_eq!(AddressableBuffer::<()>::total_len(&concat), 9);
+        assert_eq!(concat.read_slice(&mut (), 0, 9), Ok((0..9).collect()));
+        // Empty middle part is skipped.
+        assert_eq!(concat.read_slice(&mut (), 2, 2), Ok(vec![2, 3]));
+        assert_eq!(concat.read_slice(&mut (), 4, 3), Ok(vec![4, 5, 6]));
+        assert_eq!(concat.read_byte(&mut (), 8), Ok(8));
+        assert_eq!(concat.read_slice(&mut (), 9, 0), Ok(vec![]));
+    }
+
+    #[test]
+    fn combined_bounds() {
+        let concat = parts();
+        assert_eq!(
+            concat.read_slice(&mut (), 7, 3),
+            Err(BufferError::DataTooShort {
+                position: 7,
+                minimal_length: 3
+            })
+        );
+        assert_eq!(
+            concat.read_slice(&mut (), 10, 0),
+            Err(BufferError::OutOfRange {
+                position: 10,
+                total_length: 9
+            })
+        );
+        let empty = Concat::<&[u8]>::new(Vec::new());
+        assert_eq!(empty.read_slice(&mut (), 0, 0), Ok(vec![]));
+    }
+
+    #[test]
+    fn limit_length_inside_part() {
+        let concat = parts();
+        let limited = AddressableBuffer::<()>::limit_length(&concat, 4).unwrap();
+        assert_eq!(limited.parts(), [&[0, 1, 2][..], &[], &[3]]);
+        assert_eq!(limited.read_slice(&mut (), 0, 4), Ok(vec![0, 1, 2, 3]));
+        let limited = AddressableBuffer::<()>::limit_length(&concat, 3).unwrap();
+        assert_eq!(limited.parts(), [&[0, 1, 2][..]]);
+        assert_eq!(
+            AddressableBuffer::<()>::limit_length(&concat, 10),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 10
+            })
+        );
+    }
+
+    #[test]
+    fn part_errors_at_global_position() {
+        let concat = Concat::new(vec![
+            Truncated {
+                data: &[0, 1, 2],
+                claimed: 3,
+            },
+            Truncated {
+                data: &[3, 4],
+                claimed: 4,
+            },
+        ]);
+        assert_eq!(concat.read_slice(&mut (), 2, 3), Ok(vec![2, 3, 4]));
+        assert_eq!(
+            concat.read_slice(&mut (), 2, 4),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 3
+            })
+        );
+        // Length in the error is the combined length, not part length.
+        assert_eq!(
+            concat.read_slice(&mut (), 6, 1),
+            Err(BufferError::OutOfRange {
+                position: 6,
+                total_length: 7
+            })
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Sequential reading from addressable buffers.
+use core::marker::PhantomData;
+
+use crate::{AddressableBuffer, BufferError, ExternalMemory};
+
+/// Cursor for sequential reads over [`AddressableBuffer`].
+///
+/// Keeps track of current position, so that the consumers would not need to
+/// carry `position: &mut usize` around. Errors are reported with position in
+/// the buffer at which the read failed.
+#[derive(Debug)]
+pub struct BufferCursor<'b, B: AddressableBuffer<E>, E: ExternalMemory> {
+    buffer: &'b B,
+    position: usize,
+    ext_memory_type: PhantomData<E>,
+}
+
+/// Saved cursor position, to return to with [`BufferCursor::restore`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CursorCheckpoint(usize);
+
+impl<'b, B: AddressableBuffer<E>, E: ExternalMemory> BufferCursor<'b, B, E> {
+    /// New cursor at the start of the buffer.
+    pub fn new(buffer: &'b B) -> Self {
+        Self {
+            buffer,
+            position: 0,
+            ext_memory_type: PhantomData,
+        }
+    }
+
+    /// Buffer the cursor is reading from.
+    pub fn buffer(&self) -> &'b B {
+        self.buffer
+    }
+
+    /// Current position in the buffer.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of bytes remaining after current position.
+    pub fn remaining(&self) -> usize {
+        self.buffer.total_len().saturating_sub(self.position)
+    }
+
+    /// Read bytes slice of known length and move past it.
+    pub fn read_exact(
+        &mut self,
+        ext_memory: &mut E,
+        len: usize,
+    ) -> Result<B::ReadBuffer, BufferError<E>> {
+        let out = self.buffer.read_slice(ext_memory, self.position, len)?;
+        self.position += len;
+        Ok(out)
+    }
+
+    /// Read bytes into caller-provided slice and move past them.
+    pub fn read_into(&mut self, ext_memory: &mut E, dst: &mut [u8]) -> Result<(), BufferError<E>> {
+        self.buffer.read_into(ext_memory, self.position, dst)?;
+        self.position += dst.len();
+        Ok(())
+    }
+
+    /// Read fixed number of bytes into array and move past them.
+    pub fn read_array<const N: usize>(
+        &mut self,
+        ext_memory: &mut E,
+    ) -> Result<[u8; N], BufferError<E>> {
+        let out = self.buffer.read_array(ext_memory, self.position)?;
+        self.position += N;
+        Ok(out)
+    }
+
+    /// Read single byte and move past it.
+    pub fn read_byte(&mut self, ext_memory: &mut E) -> Result<u8, BufferError<E>> {
+        let out = self.buffer.read_byte(ext_memory, self.position)?;
+        self.position += 1;
+        Ok(out)
+    }
+
+    /// Read bytes slice of known length without moving the cursor.
+    pub fn peek(&self, ext_memory: &mut E, len: usize) -> Result<B::ReadBuffer, BufferError<E>> {
+        self.buffer.read_slice(ext_memory, self.position, len)
+    }
+
+    /// Move forward by `len` bytes without reading.
+    pub fn skip(&mut self, len: usize) -> Result<(), BufferError<E>> {
+        if self.remaining() < len {
+            return Err(BufferError::DataTooShort {
+                position: self.position,
+                minimal_length: len,
+            });
+        }
+        self.position += len;
+        Ok(())
+    }
+
+    /// Move to known position in the buffer.
+    ///
+    /// Position right at the end of the buffer is allowed.
+    pub fn seek(&mut self, position: usize) -> Result<(), BufferError<E>> {
+        let total_length = self.buffer.total_len();
+        if position > total_length {
+            return Err(BufferError::OutOfRange {
+                position,
+                total_length,
+            });
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    /// Save current position.
+    pub fn checkpoint(&self) -> CursorCheckpoint {
+        CursorCheckpoint(self.position)
+    }
+
+    /// Return to previously saved position.
+    pub fn restore(&mut self, checkpoint: CursorCheckpoint) {
+        self.position = checkpoint.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = &[10, 11, 12, 13, 14, 15, 16, 17];
+
+    #[test]
+    fn sequential_reads() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        assert_eq!(cursor.read_exact(&mut (), 3), Ok(&DATA[..3]));
+        assert_eq!(cursor.read_byte(&mut ()), Ok(13));
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.remaining(), 4);
+        assert_eq!(cursor.peek(&mut (), 2), Ok(&DATA[4..6]));
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(cursor.read_exact(&mut (), 4), Ok(&DATA[4..]));
+        assert_eq!(cursor.remaining(), 0);
+        assert_eq!(cursor.read_exact(&mut (), 0), Ok(&DATA[8..]));
+    }
+
+    #[test]
+    fn errors_keep_position() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        cursor.skip(6).unwrap();
+        assert_eq!(
+            cursor.read_exact(&mut (), 3),
+            Err(BufferError::DataTooShort {
+                position: 6,
+                minimal_length: 3
+            })
+        );
+        assert_eq!(
+            cursor.peek(&mut (), 3),
+            Err(BufferError::DataTooShort {
+                position: 6,
+                minimal_length: 3
+            })
+        );
+        assert_eq!(cursor.position(), 6);
+        cursor.skip(2).unwrap();
+        assert_eq!(
+            cursor.read_byte(&mut ()),
+            Err(BufferError::DataTooShort {
+                position: 8,
+                minimal_length: 1
+            })
+        );
+        assert_eq!(cursor.position(), 8);
+    }
+
+    #[test]
+    fn skip_and_seek() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        cursor.skip(5).unwrap();
+        assert_eq!(
+            cursor.skip(4),
+            Err(BufferError::DataTooShort {
+                position: 5,
+                minimal_length: 4
+            })
+        );
+        assert_eq!(cursor.position(), 5);
+        cursor.seek(1).unwrap();
+        assert_eq!(cursor.read_byte(&mut ()), Ok(11));
+        cursor.seek(8).unwrap();
+        assert_eq!(cursor.remaining(), 0);
+        assert_eq!(
+            cursor.seek(9),
+            Err(BufferError::OutOfRange {
+                position: 9,
+                total_length: 8
+            })
+        );
+        assert_eq!(cursor.position(), 8);
+    }
+
+    #[test]
+    fn checkpoint_restore() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        cursor.skip(2).unwrap();
+        let checkpoint = cursor.checkpoint();
+        assert_eq!(cursor.read_exact(&mut (), 4), Ok(&DATA[2..6]));
+        cursor.restore(checkpoint);
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(cursor.read_byte(&mut ()), Ok(12));
+        assert_eq!(cursor.checkpoint(), CursorCheckpoint(3));
+    }
+
+    #[test]
+    fn read_into_moves_cursor() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        let mut dst = [0; 3];
+        cursor.read_into(&mut (), &mut dst).unwrap();
+        assert_eq!(dst, [10, 11, 12]);
+        assert_eq!(cursor.position(), 3);
+        let mut long = [0; 6];
+        assert_eq!(
+            cursor.read_into(&mut (), &mut long),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 6
+            })
+        );
+        assert_eq!(cursor.position(), 3);
+    }
+
+    #[test]
+    fn read_array_moves_cursor() {
+        let mut cursor = BufferCursor::<&[u8], ()>::new(&DATA);
+        cursor.skip(1).unwrap();
+        assert_eq!(cursor.read_array::<2>(&mut ()), Ok([11, 12]));
+        assert_eq!(cursor.position(), 3);
+        assert_eq!(
+            cursor.read_array::<6>(&mut ()),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 6
+            })
+        );
+        assert_eq!(cursor.position(), 3);
+    }
+}
@@ -0,0 +1,299 @@
+//! Bridge to `embedded-io` traits, for `no_std` parsers.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+use embedded_io::{Error, ErrorKind, ErrorType, Read, ReadExactError, Seek, SeekFrom};
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+impl<E: ExternalMemory> Error for BufferError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            BufferError::DataTooShort { .. }
+            | BufferError::InvalidRange { .. }
+            | BufferError::Misaligned { .. }
+            | BufferError::OutOfRange { .. }
+            | BufferError::WritePastEnd { .. } => ErrorKind::InvalidInput,
+            BufferError::NonCanonicalEncoding { .. } | BufferError::OverlongEncoding { .. } => {
+                ErrorKind::InvalidData
+            }
+            BufferError::ReadOnly { .. } => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// [`AddressableBuffer`] reader implementing `embedded-io` [`Read`] and
+/// [`Seek`].
+#[derive(Debug)]
+pub struct EmbeddedIoReader<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> {
+    buffer: &'b B,
+    ext_memory: &'m mut E,
+    position: usize,
+}
+
+impl<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> EmbeddedIoReader<'b, 'm, B, E> {
+    /// New reader at the start of the buffer.
+    pub fn new(buffer: &'b B, ext_memory: &'m mut E) -> Self {
+        Self {
+            buffer,
+            ext_memory,
+            position: 0,
+        }
+    }
+
+    /// Current position in the buffer.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> ErrorType for EmbeddedIoReader<'_, '_, B, E> {
+    type Error = BufferError<E>;
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> Read for EmbeddedIoReader<'_, '_, B, E> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let read_len = buf
+            .len()
+            .min(self.buffer.total_len().saturating_sub(self.position));
+        if read_len == 0 {
+            return Ok(0);
+        }
+        self.buffer
+            .read_into(self.ext_memory, self.position, &mut buf[..read_len])?;
+        self.position += read_len;
+        Ok(read_len)
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> Seek for EmbeddedIoReader<'_, '_, B, E> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let total_length = self.buffer.total_len();
+        let (base, offset) = match pos {
+            SeekFrom::Start(offset) => (0, offset as i128),
+            SeekFrom::End(offset) => (total_length as i128, offset as i128),
+            SeekFrom::Current(offset) => (self.position as i128, offset as i128),
+        };
+        let target = base + offset;
+        // Target before the buffer start is reported as position `0`.
+        self.position = usize::try_from(target).map_err(|_| BufferError::OutOfRange {
+            position: if target < 0 { 0 } else { usize::MAX },
+            total_length,
+        })?;
+        Ok(self.position as u64)
+    }
+}
+
+/// Seekable `embedded-io` device used as [`ExternalMemory`].
+///
+/// Regions of device are [`IoRegion`] values.
+#[derive(Debug)]
+pub struct IoMemory<D> {
+    device: D,
+}
+
+impl<D: Read + Seek + Debug> IoMemory<D> {
+    /// Wrap device.
+    pub fn new(device: D) -> Self {
+        Self { device }
+    }
+
+    /// Wrapped device.
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    /// Wrapped device, mutable.
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Release wrapped device.
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
+    /// Region of device at known offset.
+    ///
+    /// Device length is not checked here, reads past the device end result
+    /// in [`IoMemoryError::UnexpectedEof`].
+    pub fn region(&self, offset: u64, length: usize) -> IoRegion {
+        IoRegion { offset, length }
+    }
+}
+
+impl<D: Read + Seek + Debug> ExternalMemory for IoMemory<D> {
+    type ExternalMemoryError = IoMemoryError;
+}
+
+/// Error accessing [`IoMemory`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IoMemoryError {
+    Device(ErrorKind),
+    UnexpectedEof,
+}
+
+impl Display for IoMemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            IoMemoryError::Device(kind) => write!(f, "Device access failed: {kind:?}."),
+            IoMemoryError::UnexpectedEof => write!(f, "Unexpected end of device data."),
+        }
+    }
+}
+
+/// Region of [`IoMemory`]: device offset and region length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IoRegion {
+    offset: u64,
+    length: usize,
+}
+
+impl<D: Read + Seek + Debug> AddressableBuffer<IoMemory<D>> for IoRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut IoMemory<D>,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<IoMemory<D>>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut IoMemory<D>,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<IoMemory<D>>> {
+        check_span(self.length, position, dst.len())?;
+        let device_position = self
+            .offset
+            .checked_add(position as u64)
+            .ok_or(BufferError::External(IoMemoryError::UnexpectedEof))?;
+        ext_memory
+            .device
+            .seek(SeekFrom::Start(device_position))
+            .map_err(|e| BufferError::External(IoMemoryError::Device(e.kind())))?;
+        ext_memory.device.read_exact(dst).map_err(|e| match e {
+            ReadExactError::UnexpectedEof => BufferError::External(IoMemoryError::UnexpectedEof),
+            ReadExactError::Other(e) => BufferError::External(IoMemoryError::Device(e.kind())),
+        })
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<IoMemory<D>>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seek_and_read() {
+        let data = [1u8, 2, 3, 4, 5];
+        let buffer = data.as_slice();
+        let mut ext_memory = ();
+        let mut reader = EmbeddedIoReader::new(&buffer, &mut ext_memory);
+        assert_eq!(reader.seek(SeekFrom::End(-2)), Ok(3));
+        let mut out = [0; 4];
+        assert_eq!(reader.read(&mut out), Ok(2));
+        assert_eq!(out[..2], [4, 5]);
+        assert_eq!(reader.read(&mut out), Ok(0));
+        assert_eq!(reader.seek(SeekFrom::Current(-4)), Ok(1));
+    }
+
+    #[test]
+    fn failed_seek_reports_target() {
+        let data = [0u8; 5];
+        let buffer = data.as_slice();
+        let mut ext_memory = ();
+        let mut reader = EmbeddedIoReader::new(&buffer, &mut ext_memory);
+        reader.seek(SeekFrom::Start(3)).unwrap();
+        assert_eq!(
+            reader.seek(SeekFrom::Current(-4)),
+            Err(BufferError::OutOfRange {
+                position: 0,
+                total_length: 5
+            })
+        );
+        assert_eq!(reader.seek(SeekFrom::Current(0)), Ok(3));
+    }
+
+    #[derive(Debug)]
+    struct RamDevice {
+        data: Vec<u8>,
+        position: u64,
+    }
+
+    impl ErrorType for RamDevice {
+        type Error = ErrorKind;
+    }
+
+    impl Read for RamDevice {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let start = (self.position as usize).min(self.data.len());
+            let read_len = buf.len().min(self.data.len() - start);
+            buf[..read_len].copy_from_slice(&self.data[start..start + read_len]);
+            self.position += read_len as u64;
+            Ok(read_len)
+        }
+    }
+
+    impl Seek for RamDevice {
+        fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+            match pos {
+                SeekFrom::Start(offset) => self.position = offset,
+                _ => return Err(ErrorKind::Unsupported),
+            }
+            Ok(self.position)
+        }
+    }
+
+    #[test]
+    fn region_reads() {
+        let mut memory = IoMemory::new(RamDevice {
+            data: vec![1, 2, 3, 4, 5, 6],
+            position: 0,
+        });
+        let region = memory.region(2, 3);
+        assert_eq!(region.read_slice(&mut memory, 1, 2).unwrap(), [4, 5]);
+        let past_end = memory.region(4, 3);
+        assert!(matches!(
+            past_end.read_slice(&mut memory, 0, 3),
+            Err(BufferError::External(IoMemoryError::UnexpectedEof))
+        ));
+    }
+
+    #[test]
+    fn region_offset_overflow() {
+        let mut memory = IoMemory::new(RamDevice {
+            data: vec![0; 4],
+            position: 0,
+        });
+        let region = memory.region(u64::MAX - 1, 4);
+        assert!(matches!(
+            region.read_slice(&mut memory, 2, 1),
+            Err(BufferError::External(IoMemoryError::UnexpectedEof))
+        ));
+    }
+}
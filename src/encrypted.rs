@@ -0,0 +1,186 @@
+//! Transparent decryption of data stored encrypted in external memory.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+
+use cipher::{Iv, Key, KeyIvInit, StreamCipher, StreamCipherSeek};
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::{AddressableBuffer, BufferError, ExternalMemory};
+
+/// [`AddressableBuffer`] adapter decrypting data of inner buffer on the fly.
+///
+/// Uses seekable stream cipher `C`, for example AES-CTR (`ctr` with `aes`
+/// crates) or ChaCha20 (`chacha20` crate), with key and nonce of the region.
+/// Keystream position is the position in the buffer, so that reads at any
+/// position return plaintext without decrypting the whole region.
+///
+/// Cipher is initialized anew for each read, so the stream cipher does not
+/// need to be `Clone`. `Debug` output does not disclose key and nonce. With
+/// `zeroize` feature, key and nonce are wiped from RAM on drop.
+pub struct EncryptedBuffer<B, C: KeyIvInit> {
+    inner: B,
+    secret: RegionSecret<C>,
+}
+
+/// Key and nonce of the region.
+struct RegionSecret<C: KeyIvInit> {
+    key: Key<C>,
+    iv: Iv<C>,
+}
+
+impl<C: KeyIvInit> Clone for RegionSecret<C> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            iv: self.iv.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<C: KeyIvInit> Drop for RegionSecret<C> {
+    fn drop(&mut self) {
+        self.key.as_mut_slice().zeroize();
+        self.iv.as_mut_slice().zeroize();
+    }
+}
+
+impl<B, C> EncryptedBuffer<B, C>
+where
+    C: KeyIvInit + StreamCipher + StreamCipherSeek,
+{
+    /// Wrap buffer, with key and nonce of the region. Keystream position `0`
+    /// corresponds to the buffer start.
+    pub fn new(inner: B, key: &Key<C>, iv: &Iv<C>) -> Self {
+        Self {
+            inner,
+            secret: RegionSecret {
+                key: key.clone(),
+                iv: iv.clone(),
+            },
+        }
+    }
+
+    /// Inner buffer, with encrypted data.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Release inner buffer.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B, C, E> AddressableBuffer<E> for EncryptedBuffer<B, C>
+where
+    B: AddressableBuffer<E>,
+    C: KeyIvInit + StreamCipher + StreamCipherSeek,
+    E: ExternalMemory,
+{
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.inner.total_len()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        self.inner.read_into(ext_memory, position, dst)?;
+        let mut cipher = C::new(&self.secret.key, &self.secret.iv);
+        cipher
+            .try_seek(position as u64)
+            .and_then(|_| cipher.try_apply_keystream(dst))
+            .map_err(|_| {
+                // Ciphertext must not be mistaken for plaintext.
+                dst.fill(0);
+                BufferError::KeystreamExhausted { position }
+            })
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        Ok(Self {
+            inner: self.inner.limit_length(new_len)?,
+            secret: self.secret.clone(),
+        })
+    }
+}
+
+impl<B: Clone, C: KeyIvInit> Clone for EncryptedBuffer<B, C> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+impl<B: Debug, C: KeyIvInit> Debug for EncryptedBuffer<B, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("EncryptedBuffer")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chacha20::ChaCha20;
+    use cipher::{KeyIvInit, StreamCipher};
+
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+    const NONCE: [u8; 12] = [9; 12];
+
+    fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+        let mut data = plaintext.to_vec();
+        ChaCha20::new(&KEY.into(), &NONCE.into()).apply_keystream(&mut data);
+        data
+    }
+
+    #[test]
+    fn random_access_decryption() {
+        let plaintext: Vec<u8> = (0..300).map(|a| a as u8).collect();
+        let ciphertext = encrypt(&plaintext);
+        let buffer =
+            EncryptedBuffer::<_, ChaCha20>::new(ciphertext.as_slice(), &KEY.into(), &NONCE.into());
+        for (position, len) in [(0, 300), (1, 63), (64, 1), (100, 150), (299, 1), (300, 0)] {
+            assert_eq!(
+                buffer.read_slice(&mut (), position, len).unwrap(),
+                plaintext[position..position + len]
+            );
+        }
+        assert_eq!(buffer.read_byte(&mut (), 200), Ok(200));
+        let limited = AddressableBuffer::<()>::limit_length(&buffer, 10).unwrap();
+        assert_eq!(AddressableBuffer::<()>::total_len(&limited), 10);
+        assert_eq!(limited.read_slice(&mut (), 5, 5).unwrap(), plaintext[5..10]);
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let data = [0u8; 4];
+        let buffer =
+            EncryptedBuffer::<_, ChaCha20>::new(data.as_slice(), &KEY.into(), &NONCE.into());
+        let debug = format!("{buffer:?}");
+        assert!(!debug.contains("key") && !debug.contains('7'));
+    }
+}
@@ -0,0 +1,243 @@
+//! Files as external memory, for host-side tools and tests.
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as FmtResult},
+    fs::File,
+    io::{Error as IoError, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+    string::ToString,
+    vec::Vec,
+};
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// File used as [`ExternalMemory`].
+///
+/// Reading goes through seek and read, similarly to external memory access
+/// on device, with all fallible paths exercised. Regions of file are
+/// [`FileRegion`] values.
+#[derive(Debug)]
+pub struct FileMemory {
+    file: File,
+}
+
+impl FileMemory {
+    /// Open file at path, read-only.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileMemoryError> {
+        Ok(Self::new(File::open(path)?))
+    }
+
+    /// Use already opened file.
+    pub fn new(file: File) -> Self {
+        Self { file }
+    }
+
+    /// File length.
+    pub fn len(&self) -> Result<usize, BufferError<Self>> {
+        let len = self
+            .file
+            .metadata()
+            .map_err(|e| BufferError::External(e.into()))?
+            .len();
+        usize::try_from(len).map_err(|_| {
+            BufferError::External(
+                IoError::new(ErrorKind::Unsupported, "File is too large to address.").into(),
+            )
+        })
+    }
+
+    /// File is empty.
+    pub fn is_empty(&self) -> Result<bool, BufferError<Self>> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Region of file, checked against the current file length.
+    pub fn region(&self, offset: usize, length: usize) -> Result<FileRegion, BufferError<Self>> {
+        let total_length = self.len()?;
+        check_span(total_length, offset, length)?;
+        Ok(FileRegion { offset, length })
+    }
+
+    /// Region covering the whole file.
+    pub fn whole(&self) -> Result<FileRegion, BufferError<Self>> {
+        self.region(0, self.len()?)
+    }
+}
+
+impl ExternalMemory for FileMemory {
+    type ExternalMemoryError = FileMemoryError;
+}
+
+/// Error accessing [`FileMemory`].
+#[derive(Debug)]
+pub struct FileMemoryError(pub IoError);
+
+impl From<IoError> for FileMemoryError {
+    fn from(error: IoError) -> Self {
+        Self(error)
+    }
+}
+
+/// Errors are considered equal if they have same kind and same description.
+impl PartialEq for FileMemoryError {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.kind() == other.0.kind() && self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl Eq for FileMemoryError {}
+
+impl Display for FileMemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "File access failed. {}", self.0)
+    }
+}
+
+impl Error for FileMemoryError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+/// Region of [`FileMemory`]: offset in file and region length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileRegion {
+    offset: usize,
+    length: usize,
+}
+
+impl FileRegion {
+    /// Region start in file.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl AddressableBuffer<FileMemory> for FileRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut FileMemory,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<FileMemory>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut FileMemory,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<FileMemory>> {
+        check_span(self.length, position, dst.len())?;
+        ext_memory
+            .file
+            .seek(SeekFrom::Start((self.offset + position) as u64))
+            .and_then(|_| ext_memory.file.read_exact(dst))
+            .map_err(|e| BufferError::External(e.into()))
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<FileMemory>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use super::*;
+
+    /// Temporary file with known contents, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &[u8]) -> Self {
+            let path = env::temp_dir().join(format!("{name}-{}", process::id()));
+            fs::write(&path, data).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn region_read() {
+        let temp = TempFile::new("file-region-read", &[0, 1, 2, 3, 4, 5, 6, 7]);
+        let mut memory = FileMemory::open(&temp.0).unwrap();
+        assert_eq!(memory.len().unwrap(), 8);
+        let region = memory.region(2, 5).unwrap();
+        assert_eq!(region.offset(), 2);
+        assert_eq!(region.total_len(), 5);
+        assert_eq!(region.read_slice(&mut memory, 1, 3).unwrap(), [3, 4, 5]);
+        assert_eq!(region.read_byte(&mut memory, 4).unwrap(), 6);
+        assert!(matches!(
+            region.read_slice(&mut memory, 3, 3),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 3
+            })
+        ));
+        let limited = region.limit_length(2).unwrap();
+        assert_eq!(limited.read_slice(&mut memory, 0, 2).unwrap(), [2, 3]);
+        assert!(region.limit_length(6).is_err());
+        let whole = memory.whole().unwrap();
+        assert_eq!(whole.read_slice(&mut memory, 6, 2).unwrap(), [6, 7]);
+    }
+
+    #[test]
+    fn region_past_end_of_file() {
+        let temp = TempFile::new("file-region-past-end", &[0, 1, 2, 3]);
+        let memory = FileMemory::open(&temp.0).unwrap();
+        assert!(matches!(
+            memory.region(2, 3),
+            Err(BufferError::DataTooShort {
+                position: 2,
+                minimal_length: 3
+            })
+        ));
+        assert!(matches!(
+            memory.region(5, 0),
+            Err(BufferError::OutOfRange {
+                position: 5,
+                total_length: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn io_error_is_external() {
+        let temp = TempFile::new("file-io-error", &[0, 1, 2, 3]);
+        let mut memory = FileMemory::open(&temp.0).unwrap();
+        let region = memory.whole().unwrap();
+        // File shrinks after the region was checked.
+        fs::write(&temp.0, [0, 1]).unwrap();
+        match region.read_slice(&mut memory, 0, 4) {
+            Err(BufferError::External(FileMemoryError(e))) => {
+                assert_eq!(e.kind(), ErrorKind::UnexpectedEof)
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+        assert!(matches!(
+            FileMemory::open(temp.0.join("missing")),
+            Err(FileMemoryError(_))
+        ));
+    }
+}
@@ -0,0 +1,430 @@
+//! Flash-style memory, erased in sectors and programmed in pages.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::ops::Range;
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory, NoEntries};
+
+/// Byte value of erased NOR flash.
+pub const NOR_ERASED_BYTE: u8 = 0xff;
+
+/// Flash memory access through [`ExternalMemory`].
+///
+/// Flash could not be written byte-by-byte: region must be erased in whole
+/// sectors first, and only then programmed in whole program units.
+///
+/// Implementors provide raw `erase_aligned` and `program_aligned` operations,
+/// checks for alignment and erased state are done in `erase_range` and
+/// `program`, that should be used by the flash consumers.
+pub trait FlashBuffer<E: ExternalMemory>: AddressableBuffer<E> {
+    /// Erase granularity, i.e. sector size.
+    fn erase_size(&self) -> usize;
+
+    /// Program granularity.
+    fn program_size(&self) -> usize;
+
+    /// Value of each byte after erase.
+    fn erased_byte(&self) -> u8 {
+        NOR_ERASED_BYTE
+    }
+
+    /// Erase sectors within range, with alignment and bounds already checked.
+    fn erase_aligned(
+        &mut self,
+        ext_memory: &mut E,
+        range: Range<usize>,
+    ) -> Result<(), BufferError<E>>;
+
+    /// Program data at position, with alignment, bounds and erased state
+    /// already checked.
+    fn program_aligned(
+        &mut self,
+        ext_memory: &mut E,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>>;
+
+    /// Erase range of the buffer.
+    ///
+    /// Range start and end must be aligned to `erase_size`.
+    fn erase_range(
+        &mut self,
+        ext_memory: &mut E,
+        range: Range<usize>,
+    ) -> Result<(), BufferError<E>> {
+        check_bounds::<E>(self.total_len(), range.start, range.end)?;
+        check_alignment::<E>(range.start, range.len(), self.erase_size())?;
+        if range.is_empty() {
+            return Ok(());
+        }
+        self.erase_aligned(ext_memory, range)
+    }
+
+    /// Program data at known position.
+    ///
+    /// Position and data length must be aligned to `program_size`, and the
+    /// target area must be erased.
+    fn program(
+        &mut self,
+        ext_memory: &mut E,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>> {
+        let end = position
+            .checked_add(data.len())
+            .ok_or(BufferError::WritePastEnd {
+                position,
+                write_length: data.len(),
+                total_length: self.total_len(),
+            })?;
+        check_bounds::<E>(self.total_len(), position, end)?;
+        check_alignment::<E>(position, data.len(), self.program_size())?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        let current = self.read_slice(ext_memory, position, data.len())?;
+        let erased_byte = self.erased_byte();
+        if let Some(offset) = current.as_ref().iter().position(|a| *a != erased_byte) {
+            return Err(BufferError::NotErased {
+                position: position + offset,
+            });
+        }
+        self.program_aligned(ext_memory, position, data)
+    }
+}
+
+fn check_bounds<E: ExternalMemory>(
+    total_length: usize,
+    start: usize,
+    end: usize,
+) -> Result<(), BufferError<E>> {
+    if start > end {
+        return Err(BufferError::InvalidRange { start, end });
+    }
+    if end > total_length {
+        return Err(BufferError::WritePastEnd {
+            position: start,
+            write_length: end - start,
+            total_length,
+        });
+    }
+    Ok(())
+}
+
+fn check_alignment<E: ExternalMemory>(
+    position: usize,
+    length: usize,
+    alignment: usize,
+) -> Result<(), BufferError<E>> {
+    if !position.is_multiple_of(alignment) || !length.is_multiple_of(alignment) {
+        return Err(BufferError::Misaligned {
+            position,
+            length,
+            alignment,
+        });
+    }
+    Ok(())
+}
+
+/// NOR flash simulated in RAM.
+///
+/// Useful for testing flash consumers. Regions of simulated flash are
+/// [`SimulatedFlashRegion`] values.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimulatedFlash {
+    data: Vec<u8>,
+    erase_size: usize,
+    program_size: usize,
+}
+
+impl SimulatedFlash {
+    /// New fully erased simulated flash.
+    ///
+    /// Capacity is rounded up to whole number of sectors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `erase_size` or `program_size` is zero, or if `erase_size`
+    /// is not a multiple of `program_size`.
+    pub fn new(capacity: usize, erase_size: usize, program_size: usize) -> Self {
+        assert!(
+            program_size != 0 && erase_size != 0 && erase_size.is_multiple_of(program_size),
+            "Erase size must be a non-zero multiple of non-zero program size."
+        );
+        let sectors = capacity.div_ceil(erase_size);
+        Self {
+            data: vec![NOR_ERASED_BYTE; sectors * erase_size],
+            erase_size,
+            program_size,
+        }
+    }
+
+    /// Total flash capacity.
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Raw flash contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Region of simulated flash, starting at sector boundary.
+    pub fn region(
+        &self,
+        offset: usize,
+        length: usize,
+    ) -> Result<SimulatedFlashRegion, BufferError<Self>> {
+        check_alignment::<Self>(offset, 0, self.erase_size)?;
+        let end = offset
+            .checked_add(length)
+            .ok_or(BufferError::DataTooShort {
+                position: offset,
+                minimal_length: length,
+            })?;
+        if end > self.capacity() {
+            return Err(BufferError::DataTooShort {
+                position: offset,
+                minimal_length: length,
+            });
+        }
+        Ok(SimulatedFlashRegion {
+            offset,
+            length,
+            erase_size: self.erase_size,
+            program_size: self.program_size,
+        })
+    }
+}
+
+impl ExternalMemory for SimulatedFlash {
+    type ExternalMemoryError = NoEntries;
+}
+
+/// Region of [`SimulatedFlash`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SimulatedFlashRegion {
+    offset: usize,
+    length: usize,
+    erase_size: usize,
+    program_size: usize,
+}
+
+impl AddressableBuffer<SimulatedFlash> for SimulatedFlashRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut SimulatedFlash,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<SimulatedFlash>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut SimulatedFlash,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<SimulatedFlash>> {
+        check_span(self.length, position, dst.len())?;
+        let start = self.offset + position;
+        dst.copy_from_slice(&ext_memory.data[start..start + dst.len()]);
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<SimulatedFlash>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            length: new_len,
+            ..*self
+        })
+    }
+}
+
+impl FlashBuffer<SimulatedFlash> for SimulatedFlashRegion {
+    fn erase_size(&self) -> usize {
+        self.erase_size
+    }
+    fn program_size(&self) -> usize {
+        self.program_size
+    }
+    fn erase_aligned(
+        &mut self,
+        ext_memory: &mut SimulatedFlash,
+        range: Range<usize>,
+    ) -> Result<(), BufferError<SimulatedFlash>> {
+        ext_memory.data[self.offset + range.start..self.offset + range.end].fill(NOR_ERASED_BYTE);
+        Ok(())
+    }
+    fn program_aligned(
+        &mut self,
+        ext_memory: &mut SimulatedFlash,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<SimulatedFlash>> {
+        let start = self.offset + position;
+        // NOR programming could only clear bits.
+        for (stored, new) in ext_memory.data[start..start + data.len()]
+            .iter_mut()
+            .zip(data)
+        {
+            *stored &= *new;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flash() -> (SimulatedFlash, SimulatedFlashRegion) {
+        let flash = SimulatedFlash::new(64, 16, 4);
+        let region = flash.region(16, 32).unwrap();
+        (flash, region)
+    }
+
+    #[test]
+    fn capacity_rounded_to_sectors() {
+        let flash = SimulatedFlash::new(20, 16, 4);
+        assert_eq!(flash.capacity(), 32);
+        assert!(flash.contents().iter().all(|a| *a == NOR_ERASED_BYTE));
+    }
+
+    #[test]
+    #[should_panic(expected = "Erase size must be a non-zero multiple")]
+    fn zero_erase_size() {
+        SimulatedFlash::new(64, 0, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Erase size must be a non-zero multiple")]
+    fn erase_size_not_multiple() {
+        SimulatedFlash::new(64, 10, 4);
+    }
+
+    #[test]
+    fn region_must_start_at_sector() {
+        let flash = SimulatedFlash::new(64, 16, 4);
+        assert_eq!(
+            flash.region(4, 8),
+            Err(BufferError::Misaligned {
+                position: 4,
+                length: 0,
+                alignment: 16
+            })
+        );
+        assert!(flash.region(48, 17).is_err());
+    }
+
+    #[test]
+    fn program_and_read() {
+        let (mut flash, mut region) = flash();
+        region.program(&mut flash, 4, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(
+            region.read_slice(&mut flash, 2, 8).unwrap(),
+            [0xff, 0xff, 1, 2, 3, 4, 0xff, 0xff]
+        );
+        assert_eq!(&flash.contents()[20..24], &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn program_misaligned() {
+        let (mut flash, mut region) = flash();
+        assert_eq!(
+            region.program(&mut flash, 2, &[0; 4]),
+            Err(BufferError::Misaligned {
+                position: 2,
+                length: 4,
+                alignment: 4
+            })
+        );
+        assert_eq!(
+            region.program(&mut flash, 4, &[0; 3]),
+            Err(BufferError::Misaligned {
+                position: 4,
+                length: 3,
+                alignment: 4
+            })
+        );
+    }
+
+    #[test]
+    fn program_not_erased() {
+        let (mut flash, mut region) = flash();
+        region
+            .program(&mut flash, 8, &[0xff, 0x00, 0xff, 0xff])
+            .unwrap();
+        assert_eq!(
+            region.program(&mut flash, 4, &[0; 8]),
+            Err(BufferError::NotErased { position: 9 })
+        );
+        region.erase_range(&mut flash, 0..16).unwrap();
+        region.program(&mut flash, 4, &[0; 8]).unwrap();
+    }
+
+    #[test]
+    fn program_past_end() {
+        let (mut flash, mut region) = flash();
+        assert_eq!(
+            region.program(&mut flash, 32, &[0; 4]),
+            Err(BufferError::WritePastEnd {
+                position: 32,
+                write_length: 4,
+                total_length: 32
+            })
+        );
+    }
+
+    #[test]
+    fn erase_checks() {
+        let (mut flash, mut region) = flash();
+        assert_eq!(
+            region.erase_range(&mut flash, 8..16),
+            Err(BufferError::Misaligned {
+                position: 8,
+                length: 8,
+                alignment: 16
+            })
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 16..0;
+        assert_eq!(
+            region.erase_range(&mut flash, reversed),
+            Err(BufferError::InvalidRange { start: 16, end: 0 })
+        );
+        assert_eq!(
+            region.erase_range(&mut flash, 16..48),
+            Err(BufferError::WritePastEnd {
+                position: 16,
+                write_length: 32,
+                total_length: 32
+            })
+        );
+    }
+
+    #[test]
+    fn erase_only_affects_region() {
+        let mut flash = SimulatedFlash::new(64, 16, 4);
+        let mut whole = flash.region(0, 64).unwrap();
+        whole.program(&mut flash, 0, &[0; 64]).unwrap();
+        let mut region = flash.region(16, 32).unwrap();
+        region.erase_range(&mut flash, 16..32).unwrap();
+        assert!(flash.contents()[..32].iter().all(|a| *a == 0));
+        assert!(flash.contents()[32..48].iter().all(|a| *a == 0xff));
+        assert!(flash.contents()[48..].iter().all(|a| *a == 0));
+    }
+}
@@ -0,0 +1,455 @@
+//! I2C EEPROM with 16-bit addressing, over `embedded-hal` I2C bus.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::fmt::Debug;
+
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+
+#[cfg(feature = "write")]
+use crate::WritableBuffer;
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Default maximum number of bytes read in single I2C transaction.
+pub const DEFAULT_I2C_TRANSFER: usize = 64;
+
+/// Number of acknowledge polls after write before giving up.
+#[cfg(feature = "write")]
+const WRITE_CYCLE_POLLS: usize = 10_000;
+
+/// I2C EEPROM with 16-bit memory addressing, used as [`ExternalMemory`].
+///
+/// Works with any `embedded-hal` [`I2c`] bus implementation, for example
+/// [`EmulatedEeprom`]. Regions of EEPROM are [`EepromRegion`] values.
+#[derive(Debug)]
+pub struct I2cEeprom<I2C> {
+    bus: I2C,
+    address: u8,
+    capacity: usize,
+    page_size: usize,
+    max_transfer: usize,
+}
+
+impl<I2C: I2c + Debug> I2cEeprom<I2C> {
+    /// New EEPROM at 7-bit `address` on the bus.
+    ///
+    /// # Panics
+    ///
+    /// Panics if page size is zero or capacity exceeds 16-bit address space.
+    pub fn new(bus: I2C, address: u8, capacity: usize, page_size: usize) -> Self {
+        assert!(page_size != 0, "Page size must be non-zero.");
+        assert!(
+            capacity <= 1 << 16,
+            "Capacity exceeds 16-bit address space."
+        );
+        Self {
+            bus,
+            address,
+            capacity,
+            page_size,
+            max_transfer: DEFAULT_I2C_TRANSFER,
+        }
+    }
+
+    /// Set maximum number of bytes transferred in single I2C transaction.
+    pub fn with_max_transfer(self, max_transfer: usize) -> Self {
+        Self {
+            max_transfer: max_transfer.max(1),
+            ..self
+        }
+    }
+
+    /// EEPROM capacity.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// EEPROM page size, maximum amount of data written at once.
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Release the bus.
+    pub fn release(self) -> I2C {
+        self.bus
+    }
+
+    /// Region of EEPROM, checked against EEPROM capacity.
+    pub fn region(&self, offset: usize, length: usize) -> Result<EepromRegion, BufferError<Self>> {
+        if offset > self.capacity || self.capacity - offset < length {
+            return Err(BufferError::DataTooShort {
+                position: offset,
+                minimal_length: length,
+            });
+        }
+        Ok(EepromRegion { offset, length })
+    }
+
+    #[cfg(feature = "write")]
+    fn wait_write_cycle(&mut self) -> Result<(), BufferError<Self>> {
+        for _ in 0..WRITE_CYCLE_POLLS {
+            match self.bus.write(self.address, &[]) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.kind() == ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address) => {}
+                Err(e) => return Err(BufferError::External(e.kind())),
+            }
+        }
+        Err(BufferError::External(ErrorKind::NoAcknowledge(
+            NoAcknowledgeSource::Address,
+        )))
+    }
+}
+
+impl<I2C: I2c + Debug> ExternalMemory for I2cEeprom<I2C> {
+    type ExternalMemoryError = ErrorKind;
+}
+
+/// Region of [`I2cEeprom`].
+///
+/// Reads are split into transactions of at most configured transfer size;
+/// with `write` feature, writes are split at EEPROM page boundaries.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EepromRegion {
+    offset: usize,
+    length: usize,
+}
+
+impl<I2C: I2c + Debug> AddressableBuffer<I2cEeprom<I2C>> for EepromRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut I2cEeprom<I2C>,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<I2cEeprom<I2C>>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut I2cEeprom<I2C>,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<I2cEeprom<I2C>>> {
+        check_span(self.length, position, dst.len())?;
+        let mut memory_address = self.offset + position;
+        for chunk in dst.chunks_mut(ext_memory.max_transfer) {
+            ext_memory
+                .bus
+                .write_read(
+                    ext_memory.address,
+                    &(memory_address as u16).to_be_bytes(),
+                    chunk,
+                )
+                .map_err(|e| BufferError::External(e.kind()))?;
+            memory_address += chunk.len();
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<I2cEeprom<I2C>>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(feature = "write")]
+impl<I2C: I2c + Debug> WritableBuffer<I2cEeprom<I2C>> for EepromRegion {
+    fn write_slice(
+        &mut self,
+        ext_memory: &mut I2cEeprom<I2C>,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<I2cEeprom<I2C>>> {
+        if self.length < position || self.length - position < data.len() {
+            return Err(BufferError::WritePastEnd {
+                position,
+                write_length: data.len(),
+                total_length: self.length,
+            });
+        }
+        let mut done = 0;
+        while done < data.len() {
+            let memory_address = self.offset + position + done;
+            let chunk_len = (ext_memory.page_size - memory_address % ext_memory.page_size)
+                .min(ext_memory.max_transfer)
+                .min(data.len() - done);
+            ext_memory
+                .bus
+                .transaction(
+                    ext_memory.address,
+                    &mut [
+                        Operation::Write(&(memory_address as u16).to_be_bytes()),
+                        Operation::Write(&data[done..done + chunk_len]),
+                    ],
+                )
+                .map_err(|e| BufferError::External(e.kind()))?;
+            ext_memory.wait_write_cycle()?;
+            done += chunk_len;
+        }
+        Ok(())
+    }
+}
+
+/// Emulated I2C EEPROM with 16-bit addressing, implementing `embedded-hal`
+/// [`I2c`] bus.
+///
+/// Emulator behaves as a typical EEPROM chip would:
+///
+/// - first two bytes written in a transaction set the memory address, the
+///   rest is written into memory,
+/// - written data wraps around within the page if it crosses the page
+///   boundary,
+/// - reads are sequential from the current memory address and wrap around
+///   at the end of memory,
+/// - after a write, chip does not acknowledge its address for configured
+///   number of transactions, emulating internal write cycle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmulatedEeprom {
+    data: Vec<u8>,
+    address: u8,
+    page_size: usize,
+    pointer: usize,
+    write_cycle_polls: u32,
+    busy_polls: u32,
+}
+
+impl EmulatedEeprom {
+    /// New blank EEPROM, filled with `0xff`, responding at 7-bit `address`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if capacity or page size is zero, or capacity exceeds 16-bit
+    /// address space.
+    pub fn new(address: u8, capacity: usize, page_size: usize) -> Self {
+        assert!(
+            capacity != 0 && page_size != 0,
+            "Capacity and page size must be non-zero."
+        );
+        assert!(
+            capacity <= 1 << 16,
+            "Capacity exceeds 16-bit address space."
+        );
+        Self {
+            data: vec![0xff; capacity],
+            address,
+            page_size,
+            pointer: 0,
+            write_cycle_polls: 0,
+            busy_polls: 0,
+        }
+    }
+
+    /// Number of transactions not acknowledged after each write.
+    pub fn with_write_cycle_polls(self, write_cycle_polls: u32) -> Self {
+        Self {
+            write_cycle_polls,
+            ..self
+        }
+    }
+
+    /// Raw EEPROM contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl ErrorType for EmulatedEeprom {
+    type Error = ErrorKind;
+}
+
+impl I2c for EmulatedEeprom {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        if address != self.address {
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        if self.busy_polls != 0 {
+            self.busy_polls -= 1;
+            return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        }
+        let capacity = self.data.len();
+        let mut address_bytes = [0; 2];
+        let mut written = 0;
+        let mut data_written = false;
+        for operation in operations.iter_mut() {
+            match operation {
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        if written < 2 {
+                            address_bytes[written] = *byte;
+                            if written == 1 {
+                                self.pointer =
+                                    u16::from_be_bytes(address_bytes) as usize % capacity;
+                            }
+                        } else {
+                            if let Some(stored) = self.data.get_mut(self.pointer) {
+                                *stored = *byte;
+                            }
+                            // Address counter rolls over within the page.
+                            let column = self.pointer % self.page_size;
+                            self.pointer += (column + 1) % self.page_size;
+                            self.pointer -= column;
+                            data_written = true;
+                        }
+                        written += 1;
+                    }
+                }
+                Operation::Read(buffer) => {
+                    written = 0;
+                    for byte in buffer.iter_mut() {
+                        *byte = self.data[self.pointer % capacity];
+                        self.pointer = (self.pointer + 1) % capacity;
+                    }
+                }
+            }
+        }
+        if data_written {
+            self.busy_polls = self.write_cycle_polls;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: u8 = 0x50;
+
+    #[test]
+    fn emulator_wrong_address() {
+        let mut device = EmulatedEeprom::new(ADDRESS, 256, 16);
+        assert_eq!(
+            device.write(0x51, &[0, 0]),
+            Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))
+        );
+    }
+
+    #[test]
+    fn emulator_write_wraps_within_page() {
+        let mut device = EmulatedEeprom::new(ADDRESS, 256, 16);
+        device.write(ADDRESS, &[0, 14, 1, 2, 3, 4]).unwrap();
+        assert_eq!(&device.contents()[14..18], &[1, 2, 0xff, 0xff]);
+        assert_eq!(&device.contents()[..2], &[3, 4]);
+    }
+
+    #[test]
+    fn emulator_sequential_read_wraps() {
+        let mut device = EmulatedEeprom::new(ADDRESS, 256, 16);
+        device.write(ADDRESS, &[0, 0, 7]).unwrap();
+        let mut read = [0; 2];
+        device.write_read(ADDRESS, &[0, 255], &mut read).unwrap();
+        assert_eq!(read, [0xff, 7]);
+        // Address pointer continues from the last read.
+        device.read(ADDRESS, &mut read).unwrap();
+        assert_eq!(read, [0xff, 0xff]);
+    }
+
+    #[test]
+    fn emulator_write_cycle() {
+        let mut device = EmulatedEeprom::new(ADDRESS, 256, 16).with_write_cycle_polls(2);
+        device.write(ADDRESS, &[0, 0, 1]).unwrap();
+        let nack = Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+        assert_eq!(device.write(ADDRESS, &[]), nack);
+        assert_eq!(device.write(ADDRESS, &[]), nack);
+        assert_eq!(device.write(ADDRESS, &[]), Ok(()));
+        // Setting address only does not start write cycle.
+        device.write(ADDRESS, &[0, 0]).unwrap();
+        assert_eq!(device.write(ADDRESS, &[]), Ok(()));
+    }
+
+    #[test]
+    fn region_checks() {
+        let eeprom = I2cEeprom::new(EmulatedEeprom::new(ADDRESS, 256, 16), ADDRESS, 256, 16);
+        assert!(eeprom.region(0, 256).is_ok());
+        assert!(matches!(
+            eeprom.region(200, 57),
+            Err(BufferError::DataTooShort { .. })
+        ));
+        assert!(matches!(
+            eeprom.region(257, 0),
+            Err(BufferError::DataTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn region_reads_in_chunks() {
+        let mut device = EmulatedEeprom::new(ADDRESS, 256, 16);
+        let data: Vec<u8> = (0..=255).collect();
+        for page in data.chunks(16).enumerate() {
+            let mut command = vec![0, (page.0 * 16) as u8];
+            command.extend_from_slice(page.1);
+            device.write(ADDRESS, &command).unwrap();
+        }
+        let mut eeprom = I2cEeprom::new(device, ADDRESS, 256, 16).with_max_transfer(7);
+        let region = eeprom.region(16, 200).unwrap();
+        assert_eq!(
+            region.read_slice(&mut eeprom, 10, 50).unwrap(),
+            data[26..76].to_vec()
+        );
+        assert!(matches!(
+            region.read_slice(&mut eeprom, 190, 11),
+            Err(BufferError::DataTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn region_read_bus_error() {
+        let mut eeprom = I2cEeprom::new(EmulatedEeprom::new(ADDRESS, 256, 16), 0x51, 256, 16);
+        let region = eeprom.region(0, 16).unwrap();
+        assert!(matches!(
+            region.read_slice(&mut eeprom, 0, 1),
+            Err(BufferError::External(ErrorKind::NoAcknowledge(
+                NoAcknowledgeSource::Address
+            )))
+        ));
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn region_writes_split_at_pages() {
+        let device = EmulatedEeprom::new(ADDRESS, 256, 16).with_write_cycle_polls(3);
+        let mut eeprom = I2cEeprom::new(device, ADDRESS, 256, 16).with_max_transfer(5);
+        let mut region = eeprom.region(32, 128).unwrap();
+        let data: Vec<u8> = (0..60).collect();
+        region.write_slice(&mut eeprom, 7, &data).unwrap();
+        assert_eq!(region.read_slice(&mut eeprom, 7, 60).unwrap(), data);
+        let device = eeprom.release();
+        assert_eq!(&device.contents()[39..99], data.as_slice());
+        assert!(device.contents()[..39].iter().all(|a| *a == 0xff));
+        assert!(device.contents()[99..].iter().all(|a| *a == 0xff));
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn region_write_past_end() {
+        let mut eeprom = I2cEeprom::new(EmulatedEeprom::new(ADDRESS, 256, 16), ADDRESS, 256, 16);
+        let mut region = eeprom.region(0, 16).unwrap();
+        assert!(matches!(
+            region.write_slice(&mut eeprom, 10, &[0; 7]),
+            Err(BufferError::WritePastEnd {
+                position: 10,
+                write_length: 7,
+                total_length: 16
+            })
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+//! Standard `Read`, `BufRead` and `Seek` over addressable buffers.
+use std::{
+    error::Error,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    io::{BufRead, Error as IoError, ErrorKind, Read, Result as IoResult, Seek, SeekFrom},
+    vec::Vec,
+};
+
+use crate::{AddressableBuffer, BufferError, ExternalMemory};
+
+/// Default size of [`IoReader`] internal buffer.
+pub const DEFAULT_IO_CHUNK: usize = 8192;
+
+/// [`AddressableBuffer`] reader, for use with regular `std` parsers.
+///
+/// Implements [`Read`], [`BufRead`] and [`Seek`]. Buffer errors are converted
+/// into [`IoError`] with [`BufferIoError`] payload; original [`BufferError`]
+/// is available through `source()` of the payload.
+#[derive(Debug)]
+pub struct IoReader<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> {
+    buffer: &'b B,
+    ext_memory: &'m mut E,
+    position: usize,
+    chunk: Vec<u8>,
+    chunk_position: usize,
+    capacity: usize,
+}
+
+impl<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> IoReader<'b, 'm, B, E> {
+    /// New reader at the start of the buffer, with default internal buffer
+    /// size.
+    pub fn new(buffer: &'b B, ext_memory: &'m mut E) -> Self {
+        Self::with_capacity(buffer, ext_memory, DEFAULT_IO_CHUNK)
+    }
+
+    /// New reader at the start of the buffer, with known internal buffer
+    /// size.
+    pub fn with_capacity(buffer: &'b B, ext_memory: &'m mut E, capacity: usize) -> Self {
+        Self {
+            buffer,
+            ext_memory,
+            position: 0,
+            chunk: Vec::new(),
+            chunk_position: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Current position in the buffer.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn remaining(&self) -> usize {
+        self.buffer.total_len().saturating_sub(self.position)
+    }
+
+    fn discard_chunk(&mut self) {
+        self.chunk.clear();
+        self.chunk_position = 0;
+    }
+}
+
+/// Error payload of [`IoError`] produced by [`IoReader`].
+#[derive(Debug, Eq, PartialEq)]
+pub struct BufferIoError<E: ExternalMemory>(pub BufferError<E>);
+
+impl<E: ExternalMemory> Display for BufferIoError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Unable to read addressable buffer. {}", self.0)
+    }
+}
+
+impl<E: ExternalMemory + 'static> Error for BufferIoError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl<E> From<BufferError<E>> for IoError
+where
+    E: ExternalMemory + 'static,
+    E::ExternalMemoryError: Send + Sync,
+{
+    fn from(error: BufferError<E>) -> Self {
+        let kind = match error {
+            BufferError::DataTooShort { .. } => ErrorKind::UnexpectedEof,
+            BufferError::OutOfRange { .. } => ErrorKind::InvalidInput,
+            _ => ErrorKind::Other,
+        };
+        IoError::new(kind, BufferIoError(error))
+    }
+}
+
+impl<B, E> Read for IoReader<'_, '_, B, E>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory + 'static,
+    E::ExternalMemoryError: Send + Sync,
+{
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
+        if self.chunk_position == self.chunk.len() && buf.len() >= self.capacity {
+            // Large reads bypass internal buffer.
+            let read_len = buf.len().min(self.remaining());
+            if read_len == 0 {
+                return Ok(0);
+            }
+            self.buffer
+                .read_into(self.ext_memory, self.position, &mut buf[..read_len])?;
+            self.position += read_len;
+            return Ok(read_len);
+        }
+        let available = self.fill_buf()?;
+        let read_len = buf.len().min(available.len());
+        buf[..read_len].copy_from_slice(&available[..read_len]);
+        self.consume(read_len);
+        Ok(read_len)
+    }
+}
+
+impl<B, E> BufRead for IoReader<'_, '_, B, E>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory + 'static,
+    E::ExternalMemoryError: Send + Sync,
+{
+    fn fill_buf(&mut self) -> IoResult<&[u8]> {
+        if self.chunk_position == self.chunk.len() {
+            let chunk_len = self.capacity.min(self.remaining());
+            self.chunk.resize(chunk_len, 0);
+            self.chunk_position = 0;
+            if chunk_len == 0 {
+                return Ok(&[]);
+            }
+            if let Err(e) = self
+                .buffer
+                .read_into(self.ext_memory, self.position, &mut self.chunk)
+            {
+                self.discard_chunk();
+                return Err(e.into());
+            }
+        }
+        Ok(&self.chunk[self.chunk_position..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let amt = amt.min(self.chunk.len() - self.chunk_position);
+        self.chunk_position += amt;
+        self.position += amt;
+    }
+}
+
+impl<B, E> Seek for IoReader<'_, '_, B, E>
+where
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.buffer.total_len() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => (self.position as u64).checked_add_signed(offset),
+        };
+        let new_position = new_position
+            .and_then(|a| usize::try_from(a).ok())
+            .ok_or_else(|| {
+                IoError::new(
+                    ErrorKind::InvalidInput,
+                    "Invalid seek to a negative or overflowing position.",
+                )
+            })?;
+        if new_position != self.position {
+            self.discard_chunk();
+            self.position = new_position;
+        }
+        Ok(new_position as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    /// Memory recording reads of buffer, as position and length.
+    #[derive(Debug, Default, Eq, PartialEq)]
+    struct LogMemory {
+        reads: Vec<(usize, usize)>,
+        fail: bool,
+    }
+
+    impl ExternalMemory for LogMemory {
+        type ExternalMemoryError = ReadFailed;
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct ReadFailed;
+
+    impl Display for ReadFailed {
+        fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+            write!(f, "Read failed.")
+        }
+    }
+
+    #[derive(Debug)]
+    struct LogBuffer;
+
+    impl AddressableBuffer<LogMemory> for LogBuffer {
+        type ReadBuffer = &'static [u8];
+        fn total_len(&self) -> usize {
+            DATA.len()
+        }
+        fn read_slice(
+            &self,
+            ext_memory: &mut LogMemory,
+            position: usize,
+            slice_len: usize,
+        ) -> Result<Self::ReadBuffer, BufferError<LogMemory>> {
+            if ext_memory.fail {
+                return Err(BufferError::External(ReadFailed));
+            }
+            ext_memory.reads.push((position, slice_len));
+            Ok(&DATA[position..position + slice_len])
+        }
+        fn limit_length(&self, _new_len: usize) -> Result<Self, BufferError<LogMemory>> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn small_reads_use_chunks() {
+        let mut memory = LogMemory::default();
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        let mut buf = [0; 3];
+        assert_eq!(reader.read(&mut buf[..2]).unwrap(), 2);
+        assert_eq!(reader.read(&mut buf[..2]).unwrap(), 2);
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [4, 5, 6]);
+        assert_eq!(reader.position(), 7);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [7, 8, 9]);
+        assert_eq!(memory.reads, [(0, 4), (4, 4), (8, 2)]);
+    }
+
+    #[test]
+    fn large_reads_bypass_chunk() {
+        let mut memory = LogMemory::default();
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        let mut buf = [0; 6];
+        assert_eq!(reader.read(&mut buf).unwrap(), 6);
+        assert_eq!(buf, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 4);
+        assert_eq!(buf[..4], [6, 7, 8, 9]);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert_eq!(memory.reads, [(0, 6), (6, 4)]);
+    }
+
+    #[test]
+    fn fill_buf_and_consume() {
+        let mut memory = LogMemory::default();
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        assert_eq!(reader.fill_buf().unwrap(), [0, 1, 2, 3]);
+        reader.consume(1);
+        assert_eq!(reader.fill_buf().unwrap(), [1, 2, 3]);
+        // Consuming more than available stops at chunk end.
+        reader.consume(10);
+        assert_eq!(reader.position(), 4);
+        assert_eq!(reader.fill_buf().unwrap(), [4, 5, 6, 7]);
+        let mut line = Vec::new();
+        reader.read_until(8, &mut line).unwrap();
+        assert_eq!(line, [4, 5, 6, 7, 8]);
+        assert_eq!(memory.reads, [(0, 4), (4, 4), (8, 2)]);
+    }
+
+    #[test]
+    fn seek_drops_chunk() {
+        let mut memory = LogMemory::default();
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        reader.fill_buf().unwrap();
+        // Seek to the current position keeps the chunk.
+        assert_eq!(reader.seek(SeekFrom::Start(0)).unwrap(), 0);
+        assert_eq!(reader.fill_buf().unwrap(), [0, 1, 2, 3]);
+        assert_eq!(reader.seek(SeekFrom::Start(2)).unwrap(), 2);
+        assert_eq!(reader.fill_buf().unwrap(), [2, 3, 4, 5]);
+        assert_eq!(reader.seek(SeekFrom::End(-3)).unwrap(), 7);
+        assert_eq!(reader.fill_buf().unwrap(), [7, 8, 9]);
+        assert_eq!(reader.seek(SeekFrom::End(5)).unwrap(), 15);
+        assert_eq!(reader.fill_buf().unwrap(), []);
+        assert_eq!(memory.reads, [(0, 4), (2, 4), (7, 3)]);
+    }
+
+    #[test]
+    fn invalid_seeks() {
+        let mut memory = LogMemory::default();
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        reader.seek(SeekFrom::Start(3)).unwrap();
+        let error = reader.seek(SeekFrom::Current(-4)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        let error = reader.seek(SeekFrom::End(-11)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        assert_eq!(reader.position(), 3);
+        reader.seek(SeekFrom::Start(u64::MAX)).unwrap();
+        let error = reader.seek(SeekFrom::Current(1)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn buffer_error_as_source() {
+        let mut memory = LogMemory {
+            fail: true,
+            ..LogMemory::default()
+        };
+        let mut reader = IoReader::with_capacity(&LogBuffer, &mut memory, 4);
+        let error = reader.read(&mut [0; 2]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Other);
+        let payload = error
+            .get_ref()
+            .unwrap()
+            .downcast_ref::<BufferIoError<LogMemory>>()
+            .unwrap();
+        assert_eq!(payload.0, BufferError::External(ReadFailed));
+        let source = payload
+            .source()
+            .unwrap()
+            .downcast_ref::<BufferError<LogMemory>>()
+            .unwrap();
+        assert_eq!(source, &BufferError::External(ReadFailed));
+        // Failed chunk is not kept.
+        assert_eq!(reader.position(), 0);
+        assert!(reader.fill_buf().is_err());
+    }
+
+    #[test]
+    fn error_kinds() {
+        let error = IoError::from(BufferError::<()>::DataTooShort {
+            position: 1,
+            minimal_length: 2,
+        });
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let error = IoError::from(BufferError::<()>::OutOfRange {
+            position: 3,
+            total_length: 2,
+        });
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -1,6 +1,7 @@
 //! Traits for handling bytes data from external memory.
 //!
-//! Currently only read functionality is supported.
+//! Read functionality is always available. Write functionality is hidden
+//! under `write` feature.
 #![no_std]
 #![deny(unused_crate_dependencies)]
 
@@ -15,6 +16,12 @@ extern crate alloc;
 #[macro_use]
 extern crate std;
 
+// Dev-dependencies used only in tests of optional features.
+#[cfg(test)]
+use {chacha20 as _, hmac as _, rand_chacha as _, sha2 as _};
+
+use core::ops::Range;
+
 #[cfg(not(feature = "std"))]
 use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
@@ -28,6 +35,113 @@ use std::{
     string::String,
 };
 
+#[cfg(feature = "authentication")]
+mod authenticated;
+#[cfg(feature = "poly1305")]
+pub use authenticated::Poly1305Authenticator;
+#[cfg(feature = "authentication")]
+pub use authenticated::{AuthenticatedBuffer, PageAuthenticator};
+
+#[cfg(feature = "block-device")]
+mod block;
+#[cfg(feature = "block-device")]
+pub use block::{
+    Block, BlockDevice, BlockMemory, BlockRegion, RamBlockDevice, RamBlockError, BLOCK_SIZE,
+};
+
+mod cache;
+pub use cache::{CacheStats, CachedBuffer};
+
+mod checksum;
+#[cfg(feature = "digest")]
+pub use checksum::digest_range;
+pub use checksum::{crc16_range, crc32_range, Crc16, Crc32, CHECKSUM_CHUNK};
+
+mod compare;
+pub use compare::{ct_eq_buffers, ct_eq_slice};
+
+mod concat;
+pub use concat::Concat;
+
+mod cursor;
+pub use cursor::{BufferCursor, CursorCheckpoint};
+
+#[cfg(feature = "embedded-io")]
+mod eio;
+#[cfg(feature = "embedded-io")]
+pub use eio::{EmbeddedIoReader, IoMemory, IoMemoryError, IoRegion};
+
+#[cfg(feature = "encryption")]
+mod encrypted;
+#[cfg(feature = "encryption")]
+pub use encrypted::EncryptedBuffer;
+
+#[cfg(feature = "std")]
+mod file;
+#[cfg(feature = "std")]
+pub use file::{FileMemory, FileMemoryError, FileRegion};
+
+#[cfg(feature = "write")]
+mod flash;
+#[cfg(feature = "write")]
+pub use flash::{FlashBuffer, SimulatedFlash, SimulatedFlashRegion, NOR_ERASED_BYTE};
+
+#[cfg(feature = "embedded-hal")]
+mod i2c_eeprom;
+#[cfg(feature = "embedded-hal")]
+pub use i2c_eeprom::{EepromRegion, EmulatedEeprom, I2cEeprom, DEFAULT_I2C_TRANSFER};
+
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "std")]
+pub use io::{BufferIoError, IoReader, DEFAULT_IO_CHUNK};
+
+#[cfg(feature = "merkle")]
+mod merkle;
+#[cfg(feature = "merkle")]
+pub use merkle::{build_merkle_tree, MerkleVerifiedBuffer, DEFAULT_MERKLE_CACHE};
+
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::{MappedBuffer, MappedFile};
+
+#[cfg(feature = "oram")]
+mod oram;
+#[cfg(feature = "oram")]
+pub use oram::{PathOram, ORAM_BUCKET_SLOTS, ORAM_DUMMY_EVICTIONS};
+
+#[cfg(feature = "scale")]
+mod scale;
+#[cfg(feature = "scale")]
+pub use scale::ScaleInput;
+
+#[cfg(feature = "zeroize")]
+mod secret;
+#[cfg(feature = "zeroize")]
+pub use secret::{SecretBuffer, SecretReadBuffer};
+
+#[cfg(feature = "spi-nor-emulator")]
+mod spi_nor;
+#[cfg(feature = "spi-nor-emulator")]
+pub use spi_nor::{SpiNorError, SpiNorFlash, SpiNorRegion};
+
+#[cfg(feature = "embedded-storage")]
+mod storage;
+#[cfg(feature = "embedded-storage")]
+pub use storage::{BufferStorage, StorageMemory, StorageRegion};
+
+mod varint;
+pub use varint::{decode_compact, decode_sleb128, decode_uleb128, Decoded};
+
+mod window;
+pub use window::Window;
+
+#[cfg(feature = "write")]
+mod write;
+#[cfg(feature = "write")]
+pub use write::{RamBuffer, ReadWriteBuffer, WritableBuffer};
+
 /// External addressable memory.
 pub trait ExternalMemory: Debug {
     /// Errors specific to memory accessing.
@@ -49,6 +163,18 @@ impl Display for NoEntries {
     }
 }
 
+/// Generate integer reading methods for [`AddressableBuffer`].
+macro_rules! read_int {
+    ($($name:ident, $ty:ty, $from_bytes:ident, $endianness:literal;)*) => {
+        $(
+            #[doc = concat!("Read ", $endianness, " endian `", stringify!($ty), "` at known position.")]
+            fn $name(&self, ext_memory: &mut E, position: usize) -> Result<$ty, BufferError<E>> {
+                Ok(<$ty>::$from_bytes(self.read_array(ext_memory, position)?))
+            }
+        )*
+    };
+}
+
 /// Bytes access through [`ExternalMemory`].
 ///
 /// Could be implemented, for example, for a combination of an address in
@@ -71,14 +197,78 @@ pub trait AddressableBuffer<E: ExternalMemory>: Sized {
         slice_len: usize,
     ) -> Result<Self::ReadBuffer, BufferError<E>>;
 
+    /// Read bytes at known position into caller-provided slice.
+    ///
+    /// Number of bytes read is the length of `dst`. Default implementation
+    /// uses `read_slice` and copies the result; implementors are encouraged
+    /// to override it with direct copying to avoid allocations.
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        let slice = self.read_slice(ext_memory, position, dst.len())?;
+        dst.copy_from_slice(slice.as_ref());
+        Ok(())
+    }
+
+    /// Read fixed number of bytes at known position into array.
+    fn read_array<const N: usize>(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+    ) -> Result<[u8; N], BufferError<E>> {
+        let mut out = [0; N];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+
+    read_int! {
+        read_u16_le, u16, from_le_bytes, "little";
+        read_u16_be, u16, from_be_bytes, "big";
+        read_u32_le, u32, from_le_bytes, "little";
+        read_u32_be, u32, from_be_bytes, "big";
+        read_u64_le, u64, from_le_bytes, "little";
+        read_u64_be, u64, from_be_bytes, "big";
+        read_u128_le, u128, from_le_bytes, "little";
+        read_u128_be, u128, from_be_bytes, "big";
+        read_i16_le, i16, from_le_bytes, "little";
+        read_i16_be, i16, from_be_bytes, "big";
+        read_i32_le, i32, from_le_bytes, "little";
+        read_i32_be, i32, from_be_bytes, "big";
+        read_i64_le, i64, from_le_bytes, "little";
+        read_i64_be, i64, from_be_bytes, "big";
+        read_i128_le, i128, from_le_bytes, "little";
+        read_i128_be, i128, from_be_bytes, "big";
+    }
+
     /// Read single byte at known position.
     fn read_byte(&self, ext_memory: &mut E, position: usize) -> Result<u8, BufferError<E>> {
-        let byte_slice = self.read_slice(ext_memory, position, 1)?;
-        Ok(byte_slice.as_ref()[0])
+        let mut byte = [0];
+        self.read_into(ext_memory, position, &mut byte)?;
+        Ok(byte[0])
     }
 
     /// Restrict the length of the addressable buffer.
     fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>>;
+
+    /// View into part of the addressable buffer, positions in view start
+    /// from `range.start`.
+    fn sub_buffer(self, range: Range<usize>) -> Result<Window<Self>, BufferError<E>> {
+        Window::new(self, range)
+    }
+
+    /// Split the addressable buffer into two views at `mid` position.
+    fn split_at(self, mid: usize) -> Result<(Window<Self>, Window<Self>), BufferError<E>>
+    where
+        Self: Clone,
+    {
+        let total_length = self.total_len();
+        let first = Window::new(self.clone(), 0..mid)?;
+        let second = Window::new(self, mid..total_length)?;
+        Ok((first, second))
+    }
 }
 
 /// `AddressableBuffer` could be also implemented for regular bytes slices.
@@ -93,19 +283,8 @@ impl<'a, E: ExternalMemory> AddressableBuffer<E> for &'a [u8] {
         position: usize,
         slice_len: usize,
     ) -> Result<Self::ReadBuffer, BufferError<E>> {
-        if self.len() < position {
-            return Err(BufferError::OutOfRange {
-                position,
-                total_length: self.len(),
-            });
-        }
-        match self.get(position..position + slice_len) {
-            Some(a) => Ok(a),
-            None => Err(BufferError::DataTooShort {
-                position,
-                minimal_length: slice_len,
-            }),
-        }
+        check_span(self.len(), position, slice_len)?;
+        Ok(&self[position..position + slice_len])
     }
     fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
         self.get(..new_len).ok_or(BufferError::DataTooShort {
@@ -123,22 +302,165 @@ pub enum BufferError<E: ExternalMemory> {
         minimal_length: usize,
     },
     External(E::ExternalMemoryError),
+    IntegrityViolation {
+        page: usize,
+    },
+    InvalidRange {
+        start: usize,
+        end: usize,
+    },
+    KeystreamExhausted {
+        position: usize,
+    },
+    Misaligned {
+        position: usize,
+        length: usize,
+        alignment: usize,
+    },
+    NonCanonicalEncoding {
+        position: usize,
+    },
+    NotErased {
+        position: usize,
+    },
+    OramStashOverflow {
+        capacity: usize,
+    },
     OutOfRange {
         position: usize,
         total_length: usize,
     },
+    OverlongEncoding {
+        position: usize,
+    },
+    ReadOnly {
+        position: usize,
+    },
+    WritePastEnd {
+        position: usize,
+        write_length: usize,
+        total_length: usize,
+    },
 }
 
 impl<E: ExternalMemory> BufferError<E> {
+    /// Same error, with positions moved forward by `offset` and data length
+    /// replaced with `total_length` of the outer buffer.
+    ///
+    /// For adapters translating positions of inner buffers into their own.
+    pub(crate) fn shift_position(self, offset: usize, total_length: usize) -> Self {
+        match self {
+            BufferError::DataTooShort {
+                position,
+                minimal_length,
+            } => BufferError::DataTooShort {
+                position: position + offset,
+                minimal_length,
+            },
+            BufferError::IntegrityViolation { page } => BufferError::IntegrityViolation { page },
+            BufferError::InvalidRange { start, end } => BufferError::InvalidRange {
+                start: start + offset,
+                end: end + offset,
+            },
+            BufferError::KeystreamExhausted { position } => BufferError::KeystreamExhausted {
+                position: position + offset,
+            },
+            BufferError::Misaligned {
+                position,
+                length,
+                alignment,
+            } => BufferError::Misaligned {
+                position: position + offset,
+                length,
+                alignment,
+            },
+            BufferError::NonCanonicalEncoding { position } => BufferError::NonCanonicalEncoding {
+                position: position + offset,
+            },
+            BufferError::NotErased { position } => BufferError::NotErased {
+                position: position + offset,
+            },
+            BufferError::OramStashOverflow { capacity } => {
+                BufferError::OramStashOverflow { capacity }
+            }
+            BufferError::OutOfRange { position, .. } => BufferError::OutOfRange {
+                position: position + offset,
+                total_length,
+            },
+            BufferError::OverlongEncoding { position } => BufferError::OverlongEncoding {
+                position: position + offset,
+            },
+            BufferError::ReadOnly { position } => BufferError::ReadOnly {
+                position: position + offset,
+            },
+            BufferError::WritePastEnd {
+                position,
+                write_length,
+                ..
+            } => BufferError::WritePastEnd {
+                position: position + offset,
+                write_length,
+                total_length,
+            },
+            BufferError::External(e) => BufferError::External(e),
+        }
+    }
+
     fn error_text(&self) -> String {
         match &self {
             BufferError::DataTooShort { position, minimal_length } => format!("Data is too short for expected content. Expected at least {minimal_length} element(s) after position {position}."),
             BufferError::External(e) => format!("Error accessing external memory. {e}"),
+            BufferError::IntegrityViolation { page } => format!("Integrity check failed for page {page}."),
+            BufferError::InvalidRange { start, end } => format!("Invalid range: start {start} is after end {end}."),
+            BufferError::KeystreamExhausted { position } => format!("Cipher keystream is exhausted at position {position}."),
+            BufferError::Misaligned { position, length, alignment } => format!("Access of {length} element(s) at position {position} is not aligned to {alignment}."),
+            BufferError::NonCanonicalEncoding { position } => format!("Variable-length integer at position {position} is not canonically encoded."),
+            BufferError::NotErased { position } => format!("Memory at position {position} is not erased."),
+            BufferError::OramStashOverflow { capacity } => format!("ORAM stash exceeded capacity of {capacity} block(s)."),
             BufferError::OutOfRange { position, total_length } => format!("Position {position} is out of range for data length {total_length}."),
+            BufferError::OverlongEncoding { position } => format!("Variable-length integer at position {position} exceeds target type width."),
+            BufferError::ReadOnly { position } => format!("Attempted to write at position {position} into read-only region."),
+            BufferError::WritePastEnd { position, write_length, total_length } => format!("Unable to write {write_length} element(s) at position {position}, data length is {total_length}."),
         }
     }
 }
 
+/// Check that `len` elements starting at `position` are within data of
+/// `total_length`.
+pub(crate) fn check_span<E: ExternalMemory>(
+    total_length: usize,
+    position: usize,
+    len: usize,
+) -> Result<(), BufferError<E>> {
+    if total_length < position {
+        return Err(BufferError::OutOfRange {
+            position,
+            total_length,
+        });
+    }
+    if total_length - position < len {
+        return Err(BufferError::DataTooShort {
+            position,
+            minimal_length: len,
+        });
+    }
+    Ok(())
+}
+
+/// Check that range is not reversed and is within data of `total_length`.
+pub(crate) fn check_range<E: ExternalMemory>(
+    total_length: usize,
+    range: &Range<usize>,
+) -> Result<(), BufferError<E>> {
+    if range.start > range.end {
+        return Err(BufferError::InvalidRange {
+            start: range.start,
+            end: range.end,
+        });
+    }
+    check_span(total_length, range.start, range.len())
+}
+
 impl<E: ExternalMemory> Display for BufferError<E> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{}", self.error_text())
@@ -151,3 +473,107 @@ impl<E: ExternalMemory> Error for BufferError<E> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = &[
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        0x10,
+    ];
+
+    /// Bytes with `-2` at the start in little endian and at the end in big
+    /// endian, for every integer width.
+    const NEGATIVE: &[u8] = &[
+        0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xfe,
+    ];
+
+    #[test]
+    fn read_into_slice() {
+        let mut dst = [0; 4];
+        DATA.read_into(&mut (), 3, &mut dst).unwrap();
+        assert_eq!(dst, [0x04, 0x05, 0x06, 0x07]);
+        DATA.read_into(&mut (), 16, &mut []).unwrap();
+        let mut long = [0xaa; 5];
+        assert_eq!(
+            DATA.read_into(&mut (), 12, &mut long),
+            Err(BufferError::DataTooShort {
+                position: 12,
+                minimal_length: 5
+            })
+        );
+        assert_eq!(long, [0xaa; 5]);
+        assert_eq!(
+            DATA.read_into(&mut (), 17, &mut []),
+            Err(BufferError::OutOfRange {
+                position: 17,
+                total_length: 16
+            })
+        );
+    }
+
+    #[test]
+    fn read_array_at_position() {
+        assert_eq!(DATA.read_array::<2>(&mut (), 14), Ok([0x0f, 0x10]));
+        assert_eq!(DATA.read_array::<0>(&mut (), 16), Ok([]));
+        assert_eq!(
+            DATA.read_array::<3>(&mut (), 14),
+            Err(BufferError::DataTooShort {
+                position: 14,
+                minimal_length: 3
+            })
+        );
+    }
+
+    #[test]
+    fn unsigned_integers() {
+        assert_eq!(DATA.read_u16_le(&mut (), 0), Ok(0x0201));
+        assert_eq!(DATA.read_u16_be(&mut (), 0), Ok(0x0102));
+        assert_eq!(DATA.read_u32_le(&mut (), 1), Ok(0x0504_0302));
+        assert_eq!(DATA.read_u32_be(&mut (), 1), Ok(0x0203_0405));
+        assert_eq!(DATA.read_u64_le(&mut (), 8), Ok(0x100f_0e0d_0c0b_0a09));
+        assert_eq!(DATA.read_u64_be(&mut (), 8), Ok(0x090a_0b0c_0d0e_0f10));
+        assert_eq!(
+            DATA.read_u128_le(&mut (), 0),
+            Ok(0x100f_0e0d_0c0b_0a09_0807_0605_0403_0201)
+        );
+        assert_eq!(
+            DATA.read_u128_be(&mut (), 0),
+            Ok(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10)
+        );
+    }
+
+    #[test]
+    fn signed_integers() {
+        let end = NEGATIVE.len();
+        assert_eq!(NEGATIVE.read_i16_le(&mut (), 0), Ok(-2));
+        assert_eq!(NEGATIVE.read_i16_be(&mut (), end - 2), Ok(-2));
+        assert_eq!(NEGATIVE.read_i32_le(&mut (), 0), Ok(-2));
+        assert_eq!(NEGATIVE.read_i32_be(&mut (), end - 4), Ok(-2));
+        assert_eq!(NEGATIVE.read_i64_le(&mut (), 0), Ok(-2));
+        assert_eq!(NEGATIVE.read_i64_be(&mut (), end - 8), Ok(-2));
+        assert_eq!(NEGATIVE.read_i128_le(&mut (), 0), Ok(-2));
+        assert_eq!(NEGATIVE.read_i128_be(&mut (), end - 16), Ok(-2));
+        assert_eq!(DATA.read_i16_be(&mut (), 0), Ok(0x0102));
+    }
+
+    #[test]
+    fn integer_past_end() {
+        assert_eq!(
+            DATA.read_u32_le(&mut (), 14),
+            Err(BufferError::DataTooShort {
+                position: 14,
+                minimal_length: 4
+            })
+        );
+        assert_eq!(
+            DATA.read_i128_be(&mut (), 1),
+            Err(BufferError::DataTooShort {
+                position: 1,
+                minimal_length: 16
+            })
+        );
+    }
+}
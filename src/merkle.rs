@@ -0,0 +1,482 @@
+//! Reads verified against trusted root of hash tree stored in external
+//! memory.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::{
+    cell::RefCell,
+    fmt::{Debug, Formatter, Result as FmtResult},
+};
+
+use digest::{Digest, Output};
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Default number of verified tree nodes kept in RAM.
+pub const DEFAULT_MERKLE_CACHE: usize = 64;
+
+/// Domain separation prefix of leaf hashes.
+const LEAF_PREFIX: u8 = 0x00;
+
+/// Domain separation prefix of inner node hashes.
+const NODE_PREFIX: u8 = 0x01;
+
+/// Hash tree of data, split into leaves of `leaf_size` bytes.
+///
+/// Returns tree nodes to be stored in external memory, and root to be kept
+/// in trusted internal memory. Nodes are stored level by level, starting
+/// from leaf hashes, root is not included. Leaf hash is `H(0x00 || leaf)`,
+/// inner node hash is `H(0x01 || left || right)`, node without pair is
+/// moved to the next level unchanged. Empty data has single empty leaf.
+///
+/// # Panics
+///
+/// Panics if leaf size is zero.
+pub fn build_merkle_tree<D: Digest>(data: &[u8], leaf_size: usize) -> (Vec<u8>, Output<D>) {
+    assert!(leaf_size != 0, "Leaf size must be non-zero.");
+    let mut level: Vec<Output<D>> = if data.is_empty() {
+        vec![leaf_hash::<D>(&[])]
+    } else {
+        data.chunks(leaf_size).map(leaf_hash::<D>).collect()
+    };
+    let mut tree = Vec::new();
+    while level.len() > 1 {
+        level.iter().for_each(|node| tree.extend_from_slice(node));
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash::<D>(left, right),
+                [single] => single.clone(),
+                _ => unreachable!("chunks are non-empty and at most 2 elements long"),
+            })
+            .collect();
+    }
+    (tree, level.remove(0))
+}
+
+fn leaf_hash<D: Digest>(data: &[u8]) -> Output<D> {
+    D::new()
+        .chain_update([LEAF_PREFIX])
+        .chain_update(data)
+        .finalize()
+}
+
+fn node_hash<D: Digest>(left: &[u8], right: &[u8]) -> Output<D> {
+    D::new()
+        .chain_update([NODE_PREFIX])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+}
+
+/// [`AddressableBuffer`] adapter verifying all leaves touched by a read up to
+/// the trusted root, before returning any data.
+///
+/// Hash tree in `tree` buffer is in [`build_merkle_tree`] format. Verified
+/// tree nodes are cached in RAM, so that verification of neighboring leaves
+/// stops at the first already verified node. Failed verification results in
+/// [`BufferError::IntegrityViolation`] with the leaf index as page.
+pub struct MerkleVerifiedBuffer<B, T, D: Digest> {
+    data: B,
+    tree: T,
+    root: Output<D>,
+    leaf_size: usize,
+    length: usize,
+    cache: RefCell<NodeCache<D>>,
+}
+
+struct NodeCache<D: Digest> {
+    nodes: Vec<(usize, Output<D>)>,
+    capacity: usize,
+}
+
+impl<D: Digest> NodeCache<D> {
+    fn get(&self, node: usize) -> Option<&Output<D>> {
+        self.nodes
+            .iter()
+            .find(|(cached, _)| *cached == node)
+            .map(|(_, hash)| hash)
+    }
+
+    fn insert(&mut self, node: usize, hash: Output<D>) {
+        if self.capacity == 0 || self.get(node).is_some() {
+            return;
+        }
+        if self.nodes.len() == self.capacity {
+            self.nodes.remove(0);
+        }
+        self.nodes.push((node, hash));
+    }
+}
+
+impl<D: Digest> Clone for NodeCache<D> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl<B, T, D: Digest> MerkleVerifiedBuffer<B, T, D> {
+    /// New verified buffer, with the trusted root.
+    ///
+    /// `tree` buffer must hold all nodes of the tree for `data`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if leaf size is zero.
+    pub fn new<E>(
+        data: B,
+        tree: T,
+        root: Output<D>,
+        leaf_size: usize,
+    ) -> Result<Self, BufferError<E>>
+    where
+        B: AddressableBuffer<E>,
+        T: AddressableBuffer<E>,
+        E: ExternalMemory,
+    {
+        assert!(leaf_size != 0, "Leaf size must be non-zero.");
+        let length = data.total_len();
+        let tree_length = tree_nodes(leaf_count(length, leaf_size)) * <D as Digest>::output_size();
+        if tree.total_len() < tree_length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: tree_length,
+            });
+        }
+        Ok(Self {
+            data,
+            tree,
+            root,
+            leaf_size,
+            length,
+            cache: RefCell::new(NodeCache {
+                nodes: Vec::new(),
+                capacity: DEFAULT_MERKLE_CACHE,
+            }),
+        })
+    }
+
+    /// Set maximum number of verified tree nodes kept in RAM. Zero disables
+    /// caching.
+    pub fn with_cache_capacity(self, capacity: usize) -> Self {
+        self.cache.borrow_mut().nodes.truncate(capacity);
+        self.cache.borrow_mut().capacity = capacity;
+        self
+    }
+
+    /// Leaf size.
+    pub fn leaf_size(&self) -> usize {
+        self.leaf_size
+    }
+
+    /// Trusted root.
+    pub fn root(&self) -> &Output<D> {
+        &self.root
+    }
+
+    /// Drop all cached verified nodes, for example if the memory was changed.
+    pub fn invalidate(&self) {
+        self.cache.borrow_mut().nodes.clear();
+    }
+
+    /// Verify leaf hash up to the first cached node or the root.
+    fn verify_leaf<E>(
+        &self,
+        ext_memory: &mut E,
+        leaf: usize,
+        mut hash: Output<D>,
+    ) -> Result<(), BufferError<E>>
+    where
+        B: AddressableBuffer<E>,
+        T: AddressableBuffer<E>,
+        E: ExternalMemory,
+    {
+        let mut path = Vec::new();
+        let mut level_start = 0;
+        let mut level_len = leaf_count(self.data.total_len(), self.leaf_size);
+        let mut index = leaf;
+        let mut sibling = Output::<D>::default();
+        loop {
+            let node = level_start + index;
+            let trusted = if level_len == 1 {
+                Some(&self.root)
+            } else {
+                None
+            };
+            let cache = self.cache.borrow();
+            if let Some(known) = trusted.or_else(|| cache.get(node)) {
+                if *known != hash {
+                    return Err(BufferError::IntegrityViolation { page: leaf });
+                }
+                break;
+            }
+            drop(cache);
+            let sibling_index = index ^ 1;
+            let parent = if sibling_index < level_len {
+                self.tree.read_into(
+                    ext_memory,
+                    (level_start + sibling_index) * <D as Digest>::output_size(),
+                    &mut sibling,
+                )?;
+                if index.is_multiple_of(2) {
+                    node_hash::<D>(&hash, &sibling)
+                } else {
+                    node_hash::<D>(&sibling, &hash)
+                }
+            } else {
+                hash.clone()
+            };
+            path.push((node, hash));
+            hash = parent;
+            level_start += level_len;
+            level_len = level_len.div_ceil(2);
+            index /= 2;
+        }
+        let mut cache = self.cache.borrow_mut();
+        for (node, hash) in path.into_iter().rev() {
+            cache.insert(node, hash);
+        }
+        Ok(())
+    }
+}
+
+impl<B, T, D, E> AddressableBuffer<E> for MerkleVerifiedBuffer<B, T, D>
+where
+    B: AddressableBuffer<E>,
+    T: AddressableBuffer<E>,
+    D: Digest,
+    E: ExternalMemory,
+{
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        check_span(self.length, position, dst.len())?;
+        if dst.is_empty() {
+            return Ok(());
+        }
+        // Full leaf is verified even if `limit_length` was applied.
+        let data_length = self.data.total_len();
+        let mut leaf_data = vec![0; self.leaf_size];
+        let first_leaf = position / self.leaf_size;
+        let last_leaf = (position + dst.len() - 1) / self.leaf_size;
+        for leaf in first_leaf..=last_leaf {
+            let leaf_start = leaf * self.leaf_size;
+            let leaf_len = self.leaf_size.min(data_length - leaf_start);
+            let leaf_data = &mut leaf_data[..leaf_len];
+            self.data.read_into(ext_memory, leaf_start, leaf_data)?;
+            self.verify_leaf(ext_memory, leaf, leaf_hash::<D>(leaf_data))?;
+            let copy_start = position.max(leaf_start);
+            let copy_end = (position + dst.len()).min(leaf_start + leaf_len);
+            dst[copy_start - position..copy_end - position]
+                .copy_from_slice(&leaf_data[copy_start - leaf_start..copy_end - leaf_start]);
+        }
+        Ok(())
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            data: self.data.limit_length(self.data.total_len())?,
+            tree: self.tree.limit_length(self.tree.total_len())?,
+            root: self.root.clone(),
+            leaf_size: self.leaf_size,
+            length: new_len,
+            cache: self.cache.clone(),
+        })
+    }
+}
+
+impl<B: Clone, T: Clone, D: Digest> Clone for MerkleVerifiedBuffer<B, T, D> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            tree: self.tree.clone(),
+            root: self.root.clone(),
+            leaf_size: self.leaf_size,
+            length: self.length,
+            cache: self.cache.clone(),
+        }
+    }
+}
+
+impl<B: Debug, T: Debug, D: Digest> Debug for MerkleVerifiedBuffer<B, T, D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("MerkleVerifiedBuffer")
+            .field("data", &self.data)
+            .field("tree", &self.tree)
+            .field("root", &self.root)
+            .field("leaf_size", &self.leaf_size)
+            .field("length", &self.length)
+            .field("cached_nodes", &self.cache.borrow().nodes.len())
+            .finish()
+    }
+}
+
+/// Number of leaves for data of known length, at least one.
+fn leaf_count(length: usize, leaf_size: usize) -> usize {
+    length.div_ceil(leaf_size).max(1)
+}
+
+/// Number of tree nodes stored in external memory, root excluded.
+fn tree_nodes(mut level_len: usize) -> usize {
+    let mut nodes = 0;
+    while level_len > 1 {
+        nodes += level_len;
+        level_len = level_len.div_ceil(2);
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use sha2::Sha256;
+
+    use super::*;
+
+    const LEAF: usize = 16;
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|a| (a * 7) as u8).collect()
+    }
+
+    fn verified<'a>(
+        data: &'a [u8],
+        tree: &'a [u8],
+        root: Output<Sha256>,
+    ) -> MerkleVerifiedBuffer<&'a [u8], &'a [u8], Sha256> {
+        MerkleVerifiedBuffer::new::<()>(data, tree, root, LEAF).unwrap()
+    }
+
+    #[test]
+    fn tree_layout() {
+        let (tree, root) = build_merkle_tree::<Sha256>(&data(40), LEAF);
+        // Three leaves, then two nodes of the next level; root is excluded.
+        assert_eq!(tree.len(), 5 * 32);
+        let leaves: Vec<Output<Sha256>> = data(40).chunks(LEAF).map(leaf_hash::<Sha256>).collect();
+        assert_eq!(&tree[..32], leaves[0].as_slice());
+        let left = node_hash::<Sha256>(&leaves[0], &leaves[1]);
+        assert_eq!(&tree[96..128], left.as_slice());
+        // Lone third leaf is promoted unchanged.
+        assert_eq!(&tree[128..], leaves[2].as_slice());
+        assert_eq!(root, node_hash::<Sha256>(&left, &leaves[2]));
+    }
+
+    #[test]
+    fn reads_verify() {
+        for len in [0, 1, 16, 17, 100, 257] {
+            let data = data(len);
+            let (tree, root) = build_merkle_tree::<Sha256>(&data, LEAF);
+            for capacity in [0, 2, DEFAULT_MERKLE_CACHE] {
+                let buffer = verified(&data, &tree, root).with_cache_capacity(capacity);
+                for position in 0..len {
+                    let read_len = 20.min(len - position);
+                    assert_eq!(
+                        buffer.read_slice(&mut (), position, read_len).unwrap(),
+                        data[position..position + read_len]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn tampered_data_detected() {
+        let data = data(100);
+        let (tree, root) = build_merkle_tree::<Sha256>(&data, LEAF);
+        for position in 0..data.len() {
+            let mut tampered = data.clone();
+            tampered[position] ^= 0x80;
+            let buffer = verified(&tampered, &tree, root);
+            assert_eq!(
+                buffer.read_byte(&mut (), position),
+                Err(BufferError::IntegrityViolation {
+                    page: position / LEAF
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_tree_detected() {
+        let data = data(100);
+        let (tree, root) = build_merkle_tree::<Sha256>(&data, LEAF);
+        let leaf_count = data.len().div_ceil(LEAF);
+        // Every stored node is on the verification path of some leaf, except
+        // for leaf hashes, that are recomputed from data.
+        for position in leaf_count * 32..tree.len() {
+            let mut tampered = tree.clone();
+            tampered[position] ^= 1;
+            let buffer = verified(&data, &tampered, root).with_cache_capacity(0);
+            assert!(matches!(
+                buffer.read_slice(&mut (), 0, data.len()),
+                Err(BufferError::IntegrityViolation { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn tampered_sibling_detected_with_warm_cache() {
+        let data = data(100);
+        let (mut tree, root) = build_merkle_tree::<Sha256>(&data, LEAF);
+        // Leaf 0 hash is used only as a sibling, when verifying leaf 1.
+        tree[0] ^= 1;
+        let buffer = verified(&data, &tree, root);
+        buffer.read_slice(&mut (), 32, 68).unwrap();
+        assert_eq!(
+            buffer.read_byte(&mut (), 16),
+            Err(BufferError::IntegrityViolation { page: 1 })
+        );
+    }
+
+    #[test]
+    fn wrong_root_detected() {
+        let data = data(100);
+        let (tree, _) = build_merkle_tree::<Sha256>(&data, LEAF);
+        let (_, other_root) = build_merkle_tree::<Sha256>(&data[1..], LEAF);
+        let buffer = verified(&data, &tree, other_root);
+        assert_eq!(
+            buffer.read_byte(&mut (), 50),
+            Err(BufferError::IntegrityViolation { page: 3 })
+        );
+    }
+
+    #[test]
+    fn short_tree_rejected() {
+        let data = data(100);
+        let (tree, root) = build_merkle_tree::<Sha256>(&data, LEAF);
+        assert!(MerkleVerifiedBuffer::<_, _, Sha256>::new::<()>(
+            data.as_slice(),
+            &tree[..tree.len() - 1],
+            root,
+            LEAF
+        )
+        .is_err());
+    }
+}
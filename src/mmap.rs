@@ -0,0 +1,163 @@
+//! Memory-mapped files, for zero-copy inspection of large images.
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{AddressableBuffer, BufferError, ExternalMemory, FileMemoryError};
+
+/// Read-only memory-mapped file.
+///
+/// Contents are accessed through [`MappedBuffer`], with the same trait
+/// surface as any other [`AddressableBuffer`].
+#[derive(Debug)]
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    /// Map file at path, read-only.
+    ///
+    /// # Safety
+    ///
+    /// File must not be modified or truncated, by this or any other process,
+    /// while mapped. Otherwise data behind already returned slices changes,
+    /// and access to truncated part could crash the process (`SIGBUS`), which
+    /// is undefined behavior.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self, FileMemoryError> {
+        // SAFETY: requirements are passed on to the caller.
+        unsafe { Self::new(&File::open(path)?) }
+    }
+
+    /// Map already opened file, read-only.
+    ///
+    /// # Safety
+    ///
+    /// File must not be modified or truncated, by this or any other process,
+    /// while mapped. Otherwise data behind already returned slices changes,
+    /// and access to truncated part could crash the process (`SIGBUS`), which
+    /// is undefined behavior.
+    pub unsafe fn new(file: &File) -> Result<Self, FileMemoryError> {
+        // SAFETY: requirements are passed on to the caller.
+        let map = unsafe { Mmap::map(file)? };
+        Ok(Self { map })
+    }
+
+    /// Mapped file length.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Mapped file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Buffer over the whole mapped file.
+    pub fn buffer(&self) -> MappedBuffer<'_> {
+        MappedBuffer { data: &self.map }
+    }
+}
+
+/// [`AddressableBuffer`] over [`MappedFile`].
+///
+/// Mapped memory is directly addressable, so any [`ExternalMemory`] could be
+/// used, typically `()`. Read buffers borrow directly from the mapping.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MappedBuffer<'a> {
+    data: &'a [u8],
+}
+
+impl<'a, E: ExternalMemory> AddressableBuffer<E> for MappedBuffer<'a> {
+    type ReadBuffer = &'a [u8];
+    fn total_len(&self) -> usize {
+        self.data.len()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        self.data.read_slice(ext_memory, position, slice_len)
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        Ok(Self {
+            data: AddressableBuffer::<E>::limit_length(&self.data, new_len)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use super::*;
+
+    /// Temporary file with known contents, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, data: &[u8]) -> Self {
+            let path = env::temp_dir().join(format!("{name}-{}", process::id()));
+            fs::write(&path, data).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn map(temp: &TempFile) -> MappedFile {
+        // SAFETY: temporary file is not modified while mapped.
+        unsafe { MappedFile::open(&temp.0) }.unwrap()
+    }
+
+    #[test]
+    fn read_slice_borrows_mapping() {
+        let temp = TempFile::new("mmap-borrow", &[0, 1, 2, 3, 4, 5, 6, 7]);
+        let file = map(&temp);
+        assert_eq!(file.len(), 8);
+        assert!(!file.is_empty());
+        let buffer = file.buffer();
+        let slice = AddressableBuffer::<()>::read_slice(&buffer, &mut (), 2, 3).unwrap();
+        assert_eq!(slice, [2, 3, 4]);
+        assert_eq!(slice.as_ptr(), file.map[2..].as_ptr());
+        assert_eq!(
+            AddressableBuffer::<()>::read_slice(&buffer, &mut (), 6, 3),
+            Err(BufferError::DataTooShort {
+                position: 6,
+                minimal_length: 3
+            })
+        );
+    }
+
+    #[test]
+    fn limit_length() {
+        let temp = TempFile::new("mmap-limit", &[0, 1, 2, 3, 4, 5, 6, 7]);
+        let file = map(&temp);
+        let buffer = file.buffer();
+        let limited = AddressableBuffer::<()>::limit_length(&buffer, 5).unwrap();
+        assert_eq!(AddressableBuffer::<()>::total_len(&limited), 5);
+        assert_eq!(
+            AddressableBuffer::<()>::read_slice(&limited, &mut (), 3, 2),
+            Ok(&file.map[3..5])
+        );
+        assert_eq!(
+            AddressableBuffer::<()>::read_slice(&limited, &mut (), 3, 3),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 3
+            })
+        );
+        assert_eq!(
+            AddressableBuffer::<()>::limit_length(&buffer, 9),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 9
+            })
+        );
+    }
+}
@@ -0,0 +1,811 @@
+//! Oblivious access to external memory, with Path ORAM.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+
+use cipher::{Iv, IvSizeUser, Key, KeyIvInit, StreamCipher};
+use rand_core::{CryptoRng, RngCore};
+
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+use crate::{BufferError, ExternalMemory, ReadWriteBuffer};
+
+/// Number of block slots in each tree bucket.
+pub const ORAM_BUCKET_SLOTS: usize = 4;
+
+/// Maximal number of dummy accesses made after an access to bring the stash
+/// back within its capacity.
+pub const ORAM_DUMMY_EVICTIONS: usize = 4;
+
+/// Slot address marking dummy slot without a block.
+const DUMMY_ADDRESS: u64 = u64::MAX;
+
+/// Length of block address stored in each slot, in bytes.
+const ADDRESS_LEN: usize = 8;
+
+/// Path ORAM over a writable buffer, hiding which logical blocks are
+/// accessed.
+///
+/// Buffer holds a binary tree of buckets, each with [`ORAM_BUCKET_SLOTS`]
+/// slots of 8-byte little-endian block address followed by block data.
+/// Each bucket is stored as a random nonce followed by slots encrypted with
+/// stream cipher `C`, and gets a fresh nonce on every write, so that real
+/// blocks are indistinguishable from dummy slots and rewritten buckets from
+/// changed ones.
+/// Each logical block is mapped to a random leaf, and every read or write
+/// reads and writes back the whole path from the root to that leaf, then
+/// remaps the block to a fresh random leaf. Observer of memory accesses sees
+/// only uniformly random paths.
+///
+/// Nonces are random, so cipher with long nonce, for example XChaCha20 or
+/// AES-CTR, is preferred. Memory contents are not authenticated. Position
+/// map and stash are kept in RAM and are lost when `PathOram` is dropped.
+/// With `zeroize` feature, key is wiped from RAM on drop.
+pub struct PathOram<B, R, C: KeyIvInit> {
+    storage: B,
+    rng: R,
+    key: OramKey<C>,
+    block_size: usize,
+    block_count: usize,
+    leaf_count: usize,
+    height: u32,
+    position_map: Vec<usize>,
+    stash: Vec<(usize, Vec<u8>)>,
+    stash_capacity: usize,
+    pending_path: Option<usize>,
+}
+
+/// Cipher key, wiped on drop with `zeroize` feature.
+struct OramKey<C: KeyIvInit>(Key<C>);
+
+#[cfg(feature = "zeroize")]
+impl<C: KeyIvInit> Drop for OramKey<C> {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl<B, R, C> PathOram<B, R, C>
+where
+    R: RngCore + CryptoRng,
+    C: KeyIvInit + StreamCipher,
+{
+    /// Buffer length needed for `block_count` blocks of `block_size` bytes.
+    pub fn required_len(block_count: usize, block_size: usize) -> usize {
+        let leaf_count = block_count.max(1).next_power_of_two();
+        (2 * leaf_count - 1) * bucket_len::<C>(block_size)
+    }
+
+    /// Format buffer as empty ORAM for `block_count` blocks of `block_size`
+    /// bytes, with at most `stash_capacity` blocks in stash between
+    /// successful accesses.
+    ///
+    /// All buckets are overwritten with encrypted dummy slots. Blocks that
+    /// were never written read as zeroes.
+    pub fn format<E>(
+        storage: B,
+        ext_memory: &mut E,
+        mut rng: R,
+        key: &Key<C>,
+        block_count: usize,
+        block_size: usize,
+        stash_capacity: usize,
+    ) -> Result<Self, BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        let required_length = Self::required_len(block_count, block_size);
+        let total_length = storage.total_len();
+        if total_length < required_length {
+            return Err(BufferError::WritePastEnd {
+                position: 0,
+                write_length: required_length,
+                total_length,
+            });
+        }
+        let leaf_count = block_count.max(1).next_power_of_two();
+        let position_map = (0..block_count)
+            .map(|_| random_leaf(&mut rng, leaf_count))
+            .collect();
+        let mut oram = Self {
+            storage,
+            rng,
+            key: OramKey(key.clone()),
+            block_size,
+            block_count,
+            leaf_count,
+            height: leaf_count.trailing_zeros(),
+            position_map,
+            stash: Vec::new(),
+            stash_capacity,
+            pending_path: None,
+        };
+        for bucket in 0..2 * leaf_count - 1 {
+            oram.write_bucket(ext_memory, bucket, encode_bucket(&[], block_size))?;
+        }
+        oram.storage.flush(ext_memory)?;
+        Ok(oram)
+    }
+
+    /// Read logical block.
+    ///
+    /// If the stash still exceeds its capacity after dummy evictions, access
+    /// has taken effect and [`BufferError::OramStashOverflow`] is returned.
+    /// If writing the path back fails, access has taken effect in stash and
+    /// the path is written again on the next access.
+    pub fn read_block<E>(
+        &mut self,
+        ext_memory: &mut E,
+        address: usize,
+    ) -> Result<Vec<u8>, BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        self.access(ext_memory, address, None)
+    }
+
+    /// Write logical block, `data` length must be equal to the block size.
+    ///
+    /// If the stash still exceeds its capacity after dummy evictions, access
+    /// has taken effect and [`BufferError::OramStashOverflow`] is returned.
+    /// If writing the path back fails, access has taken effect in stash and
+    /// the path is written again on the next access.
+    pub fn write_block<E>(
+        &mut self,
+        ext_memory: &mut E,
+        address: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        if data.len() < self.block_size {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: self.block_size,
+            });
+        }
+        if data.len() > self.block_size {
+            return Err(BufferError::WritePastEnd {
+                position: 0,
+                write_length: data.len(),
+                total_length: self.block_size,
+            });
+        }
+        self.access(ext_memory, address, Some(data)).map(|_| ())
+    }
+
+    /// Block size.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Number of logical blocks.
+    pub fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Number of blocks currently in stash.
+    pub fn stash_len(&self) -> usize {
+        self.stash.len()
+    }
+
+    /// Dummy access: read and write back path to a fresh random leaf,
+    /// evicting stash blocks without accessing any logical block.
+    ///
+    /// Observer of memory accesses could not tell it from a regular access.
+    pub fn evict<E>(&mut self, ext_memory: &mut E) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        if let Some(pending_leaf) = self.pending_path {
+            self.write_path(ext_memory, pending_leaf)?;
+        }
+        let leaf = random_leaf(&mut self.rng, self.leaf_count);
+        let stash_len = self.stash.len();
+        if let Err(error) = self.read_path(ext_memory, leaf) {
+            self.stash.truncate(stash_len);
+            return Err(error);
+        }
+        self.write_path(ext_memory, leaf)
+    }
+
+    /// Release underlying buffer. Position map and stash are dropped.
+    pub fn into_storage(self) -> B {
+        self.storage
+    }
+
+    /// Single Path ORAM access: read path into stash, read or update the
+    /// block and remap it, write path back evicting as many stash blocks as
+    /// possible.
+    ///
+    /// Block is always remapped to a fresh random leaf, so that repeated
+    /// accesses are not linkable. If the path could not be read, nothing
+    /// changes. If the write-back fails, the access has taken effect in
+    /// stash, no blocks leave stash, and the path is written again before the
+    /// next access. If the stash exceeds its capacity after the write-back,
+    /// up to [`ORAM_DUMMY_EVICTIONS`] dummy accesses are made, and if that is
+    /// not enough, [`BufferError::OramStashOverflow`] is returned with the
+    /// access taken effect and extra blocks kept in stash.
+    fn access<E>(
+        &mut self,
+        ext_memory: &mut E,
+        address: usize,
+        new_data: Option<&[u8]>,
+    ) -> Result<Vec<u8>, BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        if address >= self.block_count {
+            return Err(BufferError::OutOfRange {
+                position: address,
+                total_length: self.block_count,
+            });
+        }
+        if let Some(pending_leaf) = self.pending_path {
+            self.write_path(ext_memory, pending_leaf)?;
+        }
+
+        let leaf = self.position_map[address];
+        let stash_len = self.stash.len();
+        if let Err(error) = self.read_path(ext_memory, leaf) {
+            self.stash.truncate(stash_len);
+            return Err(error);
+        }
+
+        let found = self.stash.iter().position(|(a, _)| *a == address);
+        let data = match (found, new_data) {
+            (Some(index), Some(new_data)) => {
+                self.stash[index].1 = new_data.to_vec();
+                new_data.to_vec()
+            }
+            (Some(index), None) => self.stash[index].1.clone(),
+            (None, Some(new_data)) => {
+                self.stash.push((address, new_data.to_vec()));
+                new_data.to_vec()
+            }
+            // Block was never written, stash is not populated with zeroes.
+            (None, None) => vec![0; self.block_size],
+        };
+        self.position_map[address] = random_leaf(&mut self.rng, self.leaf_count);
+        self.write_path(ext_memory, leaf)?;
+
+        for _ in 0..ORAM_DUMMY_EVICTIONS {
+            if self.stash.len() <= self.stash_capacity {
+                break;
+            }
+            self.evict(ext_memory)?;
+        }
+        if self.stash.len() > self.stash_capacity {
+            return Err(BufferError::OramStashOverflow {
+                capacity: self.stash_capacity,
+            });
+        }
+        Ok(data)
+    }
+
+    /// Read all buckets on path to `leaf` into stash.
+    ///
+    /// Blocks already in stash are newer than their copies in memory, and
+    /// are kept.
+    fn read_path<E>(&mut self, ext_memory: &mut E, leaf: usize) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        let mut bucket_data = vec![0; bucket_len::<C>(self.block_size)];
+        for level in 0..=self.height {
+            let bucket = self.bucket_on_path(leaf, level);
+            self.read_bucket(ext_memory, bucket, &mut bucket_data)?;
+            let slots = &bucket_data[C::iv_size()..];
+            for slot in slots.chunks(ADDRESS_LEN + self.block_size) {
+                let (slot_address, data) = slot.split_at(ADDRESS_LEN);
+                let slot_address = u64::from_le_bytes(
+                    slot_address
+                        .try_into()
+                        .expect("slot address length is static"),
+                );
+                if slot_address == DUMMY_ADDRESS || slot_address >= self.block_count as u64 {
+                    continue;
+                }
+                let slot_address = slot_address as usize;
+                if self.stash.iter().all(|(a, _)| *a != slot_address) {
+                    self.stash.push((slot_address, data.to_vec()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Stash indices of blocks to place in each bucket on path to `leaf`,
+    /// indexed by level. Greedy, starting from the deepest level.
+    fn plan_eviction(&self, leaf: usize) -> Vec<Vec<usize>> {
+        let mut plan = vec![Vec::new(); self.height as usize + 1];
+        let mut placed = vec![false; self.stash.len()];
+        for level in (0..=self.height).rev() {
+            let bucket = self.bucket_on_path(leaf, level);
+            let blocks = &mut plan[level as usize];
+            for (index, (address, _)) in self.stash.iter().enumerate() {
+                if blocks.len() == ORAM_BUCKET_SLOTS {
+                    break;
+                }
+                if !placed[index]
+                    && self.bucket_on_path(self.position_map[*address], level) == bucket
+                {
+                    placed[index] = true;
+                    blocks.push(index);
+                }
+            }
+        }
+        plan
+    }
+
+    /// Write all buckets on path to `leaf`, evicting stash blocks.
+    ///
+    /// Blocks leave stash only after the whole path is written and flushed.
+    /// On failure all blocks stay in stash and path is marked as pending.
+    fn write_path<E>(&mut self, ext_memory: &mut E, leaf: usize) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        self.pending_path = Some(leaf);
+        let plan = self.plan_eviction(leaf);
+        for level in 0..=self.height {
+            let bucket = self.bucket_on_path(leaf, level);
+            let blocks: Vec<&(usize, Vec<u8>)> = plan[level as usize]
+                .iter()
+                .map(|index| &self.stash[*index])
+                .collect();
+            let encoded = encode_bucket(&blocks, self.block_size);
+            self.write_bucket(ext_memory, bucket, encoded)?;
+        }
+        self.storage.flush(ext_memory)?;
+        self.pending_path = None;
+
+        let mut evicted = vec![false; self.stash.len()];
+        for index in plan.into_iter().flatten() {
+            evicted[index] = true;
+        }
+        let mut index = 0;
+        self.stash.retain(|_| {
+            index += 1;
+            !evicted[index - 1]
+        });
+        Ok(())
+    }
+
+    /// Read and decrypt bucket, `bucket_data` is nonce followed by slots.
+    fn read_bucket<E>(
+        &self,
+        ext_memory: &mut E,
+        bucket: usize,
+        bucket_data: &mut [u8],
+    ) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        let position = bucket * bucket_data.len();
+        self.storage.read_into(ext_memory, position, bucket_data)?;
+        let (iv, slots) = bucket_data.split_at_mut(C::iv_size());
+        C::new(&self.key.0, Iv::<C>::from_slice(iv))
+            .try_apply_keystream(slots)
+            .map_err(|_| BufferError::KeystreamExhausted { position })
+    }
+
+    /// Encrypt slots with a fresh random nonce and write bucket.
+    fn write_bucket<E>(
+        &mut self,
+        ext_memory: &mut E,
+        bucket: usize,
+        slots: Vec<u8>,
+    ) -> Result<(), BufferError<E>>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        let mut bucket_data = vec![0; C::iv_size()];
+        self.rng.fill_bytes(&mut bucket_data);
+        bucket_data.extend_from_slice(&slots);
+        let position = bucket * bucket_data.len();
+        let (iv, slots) = bucket_data.split_at_mut(C::iv_size());
+        C::new(&self.key.0, Iv::<C>::from_slice(iv))
+            .try_apply_keystream(slots)
+            .map_err(|_| BufferError::KeystreamExhausted { position })?;
+        self.storage.write_slice(ext_memory, position, &bucket_data)
+    }
+
+    /// Index of bucket at `level` (root is level `0`) on path to `leaf`, in
+    /// level-order tree layout.
+    fn bucket_on_path(&self, leaf: usize, level: u32) -> usize {
+        ((self.leaf_count + leaf) >> (self.height - level)) - 1
+    }
+}
+
+impl<B: Debug, R, C: KeyIvInit> Debug for PathOram<B, R, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("PathOram")
+            .field("storage", &self.storage)
+            .field("block_size", &self.block_size)
+            .field("block_count", &self.block_count)
+            .field("stash_len", &self.stash.len())
+            .field("stash_capacity", &self.stash_capacity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Bucket length in memory: nonce and slots.
+fn bucket_len<C: IvSizeUser>(block_size: usize) -> usize {
+    C::iv_size() + ORAM_BUCKET_SLOTS * (ADDRESS_LEN + block_size)
+}
+
+/// Uniformly random leaf, `leaf_count` is a power of two.
+fn random_leaf<R: RngCore>(rng: &mut R, leaf_count: usize) -> usize {
+    (rng.next_u64() & (leaf_count as u64 - 1)) as usize
+}
+
+/// Bucket bytes with known blocks, remaining slots are dummy.
+fn encode_bucket(blocks: &[&(usize, Vec<u8>)], block_size: usize) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(ORAM_BUCKET_SLOTS * (ADDRESS_LEN + block_size));
+    for slot in 0..ORAM_BUCKET_SLOTS {
+        match blocks.get(slot) {
+            Some(&(address, data)) => {
+                encoded.extend_from_slice(&(*address as u64).to_le_bytes());
+                encoded.extend_from_slice(data);
+            }
+            None => {
+                encoded.extend_from_slice(&DUMMY_ADDRESS.to_le_bytes());
+                encoded.resize(encoded.len() + block_size, 0);
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use core::fmt::Display;
+
+    use chacha20::ChaCha20;
+    use rand_chacha::{rand_core::SeedableRng, ChaCha8Rng};
+
+    use super::*;
+    use crate::{AddressableBuffer, RamBuffer, WritableBuffer};
+
+    const BLOCK_SIZE: usize = 16;
+
+    type TestOram<B> = PathOram<B, ChaCha8Rng, ChaCha20>;
+
+    fn format<B, E>(
+        storage: B,
+        ext_memory: &mut E,
+        block_count: usize,
+        capacity: usize,
+    ) -> TestOram<B>
+    where
+        B: ReadWriteBuffer<E>,
+        E: ExternalMemory,
+    {
+        PathOram::format(
+            storage,
+            ext_memory,
+            ChaCha8Rng::seed_from_u64(1),
+            &[0x42; 32].into(),
+            block_count,
+            BLOCK_SIZE,
+            capacity,
+        )
+        .unwrap()
+    }
+
+    fn ram_oram(block_count: usize, capacity: usize) -> TestOram<RamBuffer> {
+        let storage = RamBuffer::new(TestOram::<RamBuffer>::required_len(block_count, BLOCK_SIZE));
+        format(storage, &mut (), block_count, capacity)
+    }
+
+    fn block(seed: u32) -> Vec<u8> {
+        (0..BLOCK_SIZE as u32)
+            .map(|a| (seed.wrapping_mul(31) ^ a) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn round_trip_against_model() {
+        let block_count = 50;
+        let mut oram = ram_oram(block_count, 20);
+        let mut model = vec![vec![0; BLOCK_SIZE]; block_count];
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        for step in 0..1000 {
+            let address = rng.next_u32() as usize % block_count;
+            if rng.next_u32() % 2 == 0 {
+                oram.write_block(&mut (), address, &block(step)).unwrap();
+                model[address] = block(step);
+            } else {
+                assert_eq!(oram.read_block(&mut (), address).unwrap(), model[address]);
+            }
+            assert!(oram.stash_len() <= 20);
+        }
+        for (address, data) in model.iter().enumerate() {
+            assert_eq!(&oram.read_block(&mut (), address).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn unwritten_blocks_read_as_zeroes() {
+        let mut oram = ram_oram(8, 4);
+        for address in 0..8 {
+            assert_eq!(oram.read_block(&mut (), address).unwrap(), [0; BLOCK_SIZE]);
+        }
+        assert_eq!(oram.stash_len(), 0);
+    }
+
+    #[test]
+    fn argument_checks() {
+        let mut oram = ram_oram(8, 4);
+        assert_eq!(
+            oram.read_block(&mut (), 8),
+            Err(BufferError::OutOfRange {
+                position: 8,
+                total_length: 8
+            })
+        );
+        assert_eq!(
+            oram.write_block(&mut (), 0, &[0; BLOCK_SIZE - 1]),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: BLOCK_SIZE
+            })
+        );
+        assert_eq!(
+            oram.write_block(&mut (), 0, &[0; BLOCK_SIZE + 1]),
+            Err(BufferError::WritePastEnd {
+                position: 0,
+                write_length: BLOCK_SIZE + 1,
+                total_length: BLOCK_SIZE
+            })
+        );
+        let required_length = TestOram::<RamBuffer>::required_len(8, BLOCK_SIZE);
+        assert_eq!(
+            PathOram::<_, _, ChaCha20>::format(
+                RamBuffer::new(required_length - 1),
+                &mut (),
+                ChaCha8Rng::seed_from_u64(1),
+                &[0; 32].into(),
+                8,
+                BLOCK_SIZE,
+                4,
+            )
+            .map(|_| ()),
+            Err(BufferError::WritePastEnd {
+                position: 0,
+                write_length: required_length,
+                total_length: required_length - 1
+            })
+        );
+    }
+
+    #[test]
+    fn storage_hides_contents() {
+        let mut oram = ram_oram(4, 4);
+        let secret = [0xa5; BLOCK_SIZE];
+        for address in 0..4 {
+            oram.write_block(&mut (), address, &secret).unwrap();
+        }
+        let before = oram.storage.as_slice().to_vec();
+        assert!(before.windows(4).all(|a| a != [0xa5; 4]));
+        assert!(before.windows(8).all(|a| a != DUMMY_ADDRESS.to_le_bytes()));
+
+        // Reading rewrites the whole path with fresh nonces, including root.
+        oram.read_block(&mut (), 0).unwrap();
+        let after = oram.storage.as_slice();
+        let bucket_len = bucket_len::<ChaCha20>(BLOCK_SIZE);
+        assert_ne!(before[..bucket_len], after[..bucket_len]);
+    }
+
+    #[test]
+    fn stash_overflow_remaps_to_fresh_leaf() {
+        let block_count = 32;
+        let mut oram = ram_oram(block_count, 0);
+        // All blocks are in stash and mapped to the same leaf, with more
+        // blocks than the path could hold.
+        for address in 0..block_count {
+            oram.stash.push((address, block(address as u32)));
+            oram.position_map[address] = 0;
+        }
+        let mut model: Vec<Vec<u8>> = (0..block_count as u32).map(block).collect();
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        let mut overflows = 0;
+        let mut same_leaf = 0;
+        for step in 0..100 {
+            let address = rng.next_u32() as usize % block_count;
+            let leaf = oram.position_map[address];
+            match oram.write_block(&mut (), address, &block(step + 100)) {
+                Ok(()) => assert_eq!(oram.stash_len(), 0),
+                Err(error) => {
+                    assert_eq!(error, BufferError::OramStashOverflow { capacity: 0 });
+                    overflows += 1;
+                    if oram.position_map[address] == leaf {
+                        same_leaf += 1;
+                    }
+                }
+            }
+            // Write takes effect even if the stash overflows.
+            model[address] = block(step + 100);
+        }
+        assert!(overflows > 0);
+        // Fresh leaf could match the old one only by chance.
+        assert!(same_leaf <= overflows / 8);
+
+        // Capacity is raised so that checking reads do not overflow.
+        oram.stash_capacity = block_count;
+        for (address, data) in model.iter().enumerate() {
+            assert_eq!(&oram.read_block(&mut (), address).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn dummy_evictions_drain_stash() {
+        let block_count = 64;
+        let mut oram = ram_oram(block_count, block_count);
+        for address in 0..block_count {
+            oram.write_block(&mut (), address, &block(address as u32))
+                .unwrap();
+        }
+        let stash_len = oram.stash_len();
+        let before = oram.storage.as_slice().to_vec();
+        oram.evict(&mut ()).unwrap();
+        assert!(oram.stash_len() <= stash_len);
+        assert_ne!(oram.storage.as_slice(), before);
+        for address in 0..block_count {
+            assert_eq!(
+                oram.read_block(&mut (), address).unwrap(),
+                block(address as u32)
+            );
+        }
+    }
+
+    /// Memory failing the access with known number, counted from `1`.
+    #[derive(Debug, Default)]
+    struct FaultyMemory {
+        fail_at: Option<usize>,
+    }
+
+    impl FaultyMemory {
+        fn tick(&mut self) -> Result<(), BufferError<Self>> {
+            match self.fail_at {
+                Some(1) => {
+                    self.fail_at = None;
+                    Err(BufferError::External(InjectedFault))
+                }
+                Some(ref mut countdown) => {
+                    *countdown -= 1;
+                    Ok(())
+                }
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl ExternalMemory for FaultyMemory {
+        type ExternalMemoryError = InjectedFault;
+    }
+
+    #[derive(Debug, Eq, PartialEq)]
+    struct InjectedFault;
+
+    impl Display for InjectedFault {
+        fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+            write!(f, "Injected fault.")
+        }
+    }
+
+    /// [`RamBuffer`] with reads, writes and flushes failing as scheduled in
+    /// [`FaultyMemory`].
+    #[derive(Debug)]
+    struct FaultyBuffer(RamBuffer);
+
+    impl AddressableBuffer<FaultyMemory> for FaultyBuffer {
+        type ReadBuffer = Vec<u8>;
+        fn total_len(&self) -> usize {
+            AddressableBuffer::<FaultyMemory>::total_len(&self.0)
+        }
+        fn read_slice(
+            &self,
+            ext_memory: &mut FaultyMemory,
+            position: usize,
+            slice_len: usize,
+        ) -> Result<Self::ReadBuffer, BufferError<FaultyMemory>> {
+            ext_memory.tick()?;
+            self.0.read_slice(ext_memory, position, slice_len)
+        }
+        fn read_into(
+            &self,
+            ext_memory: &mut FaultyMemory,
+            position: usize,
+            dst: &mut [u8],
+        ) -> Result<(), BufferError<FaultyMemory>> {
+            ext_memory.tick()?;
+            self.0.read_into(ext_memory, position, dst)
+        }
+        fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<FaultyMemory>> {
+            Ok(Self(self.0.limit_length(new_len)?))
+        }
+    }
+
+    impl WritableBuffer<FaultyMemory> for FaultyBuffer {
+        fn write_slice(
+            &mut self,
+            ext_memory: &mut FaultyMemory,
+            position: usize,
+            data: &[u8],
+        ) -> Result<(), BufferError<FaultyMemory>> {
+            ext_memory.tick()?;
+            self.0.write_slice(ext_memory, position, data)
+        }
+        fn flush(
+            &mut self,
+            ext_memory: &mut FaultyMemory,
+        ) -> Result<(), BufferError<FaultyMemory>> {
+            ext_memory.tick()
+        }
+    }
+
+    #[test]
+    fn injected_faults_keep_blocks() {
+        let block_count = 32;
+        let mut memory = FaultyMemory::default();
+        let storage = FaultyBuffer(RamBuffer::new(TestOram::<FaultyBuffer>::required_len(
+            block_count,
+            BLOCK_SIZE,
+        )));
+        let mut oram = format(storage, &mut memory, block_count, 40);
+        let mut model = vec![vec![0; BLOCK_SIZE]; block_count];
+        let mut rng = ChaCha8Rng::seed_from_u64(4);
+        let mut faults = 0;
+        for step in 0..1000 {
+            let address = rng.next_u32() as usize % block_count;
+            // Path of 6 buckets: 6 reads, 6 writes and a flush, sometimes
+            // preceded by pending path write-back.
+            memory.fail_at = Some(1 + rng.next_u32() as usize % 26);
+            if rng.next_u32() % 2 == 0 {
+                match oram.write_block(&mut memory, address, &block(step)) {
+                    Ok(()) => model[address] = block(step),
+                    Err(error) => {
+                        assert!(matches!(error, BufferError::External(InjectedFault)));
+                        faults += 1;
+                        // Write either took effect or not, check which.
+                        memory.fail_at = None;
+                        let read = oram.read_block(&mut memory, address).unwrap();
+                        assert!(read == model[address] || read == block(step));
+                        model[address] = read;
+                    }
+                }
+            } else {
+                match oram.read_block(&mut memory, address) {
+                    Ok(read) => assert_eq!(read, model[address]),
+                    Err(error) => {
+                        assert!(matches!(error, BufferError::External(InjectedFault)));
+                        faults += 1;
+                    }
+                }
+            }
+        }
+        assert!(faults > 300);
+        memory.fail_at = None;
+        for (address, data) in model.iter().enumerate() {
+            assert_eq!(&oram.read_block(&mut memory, address).unwrap(), data);
+        }
+        assert!(oram.stash_len() <= 40);
+    }
+}
@@ -0,0 +1,126 @@
+//! SCALE codec input from addressable buffers.
+use parity_scale_codec::{Error as CodecError, Input};
+
+use crate::{AddressableBuffer, BufferCursor, BufferError, ExternalMemory};
+
+/// [`Input`] implementation over [`AddressableBuffer`], to decode SCALE
+/// encoded data directly from external memory.
+///
+/// Buffer errors are converted into codec errors with buffer error text,
+/// including position, chained. Latest buffer error is also kept and could
+/// be retrieved with [`ScaleInput::take_error`].
+#[derive(Debug)]
+pub struct ScaleInput<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> {
+    cursor: BufferCursor<'b, B, E>,
+    ext_memory: &'m mut E,
+    error: Option<BufferError<E>>,
+}
+
+impl<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> ScaleInput<'b, 'm, B, E> {
+    /// New input, starting at the start of the buffer.
+    pub fn new(buffer: &'b B, ext_memory: &'m mut E) -> Self {
+        Self::from_cursor(BufferCursor::new(buffer), ext_memory)
+    }
+
+    /// New input, starting at the current cursor position.
+    pub fn from_cursor(cursor: BufferCursor<'b, B, E>, ext_memory: &'m mut E) -> Self {
+        Self {
+            cursor,
+            ext_memory,
+            error: None,
+        }
+    }
+
+    /// Current position in the buffer.
+    pub fn position(&self) -> usize {
+        self.cursor.position()
+    }
+
+    /// Latest buffer error, if any.
+    pub fn take_error(&mut self) -> Option<BufferError<E>> {
+        self.error.take()
+    }
+
+    /// Release cursor, positioned right after decoded data.
+    pub fn into_cursor(self) -> BufferCursor<'b, B, E> {
+        self.cursor
+    }
+
+    fn codec_error(&mut self, error: BufferError<E>) -> CodecError {
+        let codec_error =
+            CodecError::from("Unable to read SCALE input from buffer.").chain(error.error_text());
+        self.error = Some(error);
+        codec_error
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> Input for ScaleInput<'_, '_, B, E> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, CodecError> {
+        Ok(Some(self.cursor.remaining()))
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), CodecError> {
+        self.cursor
+            .read_into(self.ext_memory, into)
+            .map_err(|e| self.codec_error(e))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, CodecError> {
+        self.cursor
+            .read_byte(self.ext_memory)
+            .map_err(|e| self.codec_error(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    #[cfg(feature = "std")]
+    use std::vec::Vec;
+
+    use parity_scale_codec::{Compact, Decode, Encode};
+
+    use super::*;
+
+    #[test]
+    fn decode_tuple() {
+        let value = (0x1234u16, Compact(1_000_000u64), vec![7u8, 8, 9], true);
+        let mut data = value.encode();
+        data.push(0xaa);
+        let buffer = data.as_slice();
+        let mut ext_memory = ();
+        let mut input = ScaleInput::new(&buffer, &mut ext_memory);
+        assert_eq!(
+            <(u16, Compact<u64>, Vec<u8>, bool)>::decode(&mut input),
+            Ok(value)
+        );
+        assert_eq!(input.position(), data.len() - 1);
+        assert_eq!(input.remaining_len(), Ok(Some(1)));
+        assert_eq!(input.take_error(), None);
+        let mut cursor = input.into_cursor();
+        assert_eq!(cursor.read_byte(&mut ()), Ok(0xaa));
+    }
+
+    #[test]
+    fn truncated_read() {
+        let data = [0xff, 1, 2, 3, 4, 5];
+        let buffer = data.as_slice();
+        let mut cursor = BufferCursor::new(&buffer);
+        cursor.skip(1).unwrap();
+        let mut ext_memory = ();
+        let mut input = ScaleInput::from_cursor(cursor, &mut ext_memory);
+        assert!(u64::decode(&mut input).is_err());
+        assert_eq!(
+            input.take_error(),
+            Some(BufferError::DataTooShort {
+                position: 1,
+                minimal_length: 8
+            })
+        );
+        assert_eq!(input.take_error(), None);
+        assert_eq!(input.position(), 1);
+        assert_eq!(u32::decode(&mut input), Ok(0x0403_0201));
+    }
+}
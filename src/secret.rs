@@ -0,0 +1,153 @@
+//! Wiping of secret material read from external memory.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    hint::black_box,
+};
+
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+use crate::{compare::accumulate, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Bytes read from buffer, wiped from RAM on drop.
+///
+/// `Debug` output does not disclose the contents. Comparison with `==` runs
+/// in time independent of contents, lengths are not secret.
+#[derive(Clone)]
+pub struct SecretReadBuffer(Zeroizing<Vec<u8>>);
+
+impl SecretReadBuffer {
+    /// Zero-filled buffer of known length, allocated exactly once.
+    fn zeroed(len: usize) -> Self {
+        Self(Zeroizing::new(vec![0; len]))
+    }
+
+    /// Number of bytes in buffer.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl AsRef<[u8]> for SecretReadBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Zeroize for SecretReadBuffer {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl ZeroizeOnDrop for SecretReadBuffer {}
+
+impl PartialEq for SecretReadBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && black_box(accumulate(0, &self.0, &other.0)) == 0
+    }
+}
+
+impl Eq for SecretReadBuffer {}
+
+impl Debug for SecretReadBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "SecretReadBuffer([REDACTED; {}])", self.len())
+    }
+}
+
+/// [`AddressableBuffer`] adapter returning all read data in
+/// [`SecretReadBuffer`].
+///
+/// Data is read directly into the zeroize-on-drop container with
+/// `read_into` of the inner buffer. Inner buffers relying on default
+/// `read_into` implementation produce intermediate `read_slice` output,
+/// that is not wiped by this adapter; for secret material, inner buffer
+/// should implement `read_into` without intermediate copies.
+///
+/// `Debug` output does not disclose the inner buffer.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub struct SecretBuffer<B> {
+    inner: B,
+}
+
+impl<B> SecretBuffer<B> {
+    /// Wrap buffer.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    /// Release inner buffer.
+    pub fn into_inner(self) -> B {
+        self.inner
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> AddressableBuffer<E> for SecretBuffer<B> {
+    type ReadBuffer = SecretReadBuffer;
+    fn total_len(&self) -> usize {
+        self.inner.total_len()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        let mut out = SecretReadBuffer::zeroed(slice_len);
+        self.inner.read_into(ext_memory, position, &mut out.0)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        self.inner.read_into(ext_memory, position, dst)
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        Ok(Self {
+            inner: self.inner.limit_length(new_len)?,
+        })
+    }
+}
+
+impl<B> Debug for SecretBuffer<B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "SecretBuffer([REDACTED])")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted_and_compared() {
+        let data = [1u8, 2, 3];
+        let buffer = SecretBuffer::new(data.as_slice());
+        let first = buffer.read_slice(&mut (), 0, 3).unwrap();
+        assert_eq!(first.as_ref(), &data);
+        assert_eq!(format!("{first:?}"), "SecretReadBuffer([REDACTED; 3])");
+        assert!(first == buffer.read_slice(&mut (), 0, 3).unwrap());
+        assert!(first != buffer.read_slice(&mut (), 0, 2).unwrap());
+        let other = [1u8, 2, 4];
+        assert!(
+            first
+                != SecretBuffer::new(other.as_slice())
+                    .read_slice(&mut (), 0, 3)
+                    .unwrap()
+        );
+    }
+}
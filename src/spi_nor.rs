@@ -0,0 +1,559 @@
+//! SPI NOR flash chip emulator, for testing drivers without hardware.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(feature = "write")]
+use core::ops::Range;
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+#[cfg(feature = "write")]
+use crate::FlashBuffer;
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Emulated SPI NOR flash chip with 24-bit addressing.
+///
+/// Emulator is driven by command-level transactions, see
+/// [`SpiNorFlash::transaction`]. Supported commands are `READ`, `FAST_READ`,
+/// `WREN`, `WRDI`, `PAGE_PROGRAM`, `SECTOR_ERASE` and `RDSR`.
+///
+/// Flash behaves as a typical chip would:
+///
+/// - program and erase require write enable latch, that is reset after the
+///   operation is finished,
+/// - program could only clear bits, and wraps around within the page if the
+///   data crosses page boundary,
+/// - erase sets whole sector to `0xff`,
+/// - reads wrap around at the end of chip,
+/// - program and erase keep the chip busy for configured number of status
+///   register polls.
+///
+/// Where a real chip would silently ignore an improper command, emulator
+/// returns [`SpiNorError`], so that the driver bugs are caught early.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SpiNorFlash {
+    data: Vec<u8>,
+    write_enabled: bool,
+    busy_polls: u32,
+    program_polls: u32,
+    erase_polls: u32,
+}
+
+impl SpiNorFlash {
+    /// Page size, maximum amount of data programmed at once.
+    pub const PAGE_SIZE: usize = 256;
+    /// Sector size, minimal erase unit.
+    pub const SECTOR_SIZE: usize = 4096;
+    /// Maximum capacity addressable with 24-bit address.
+    pub const MAX_CAPACITY: usize = 1 << 24;
+
+    pub const CMD_PAGE_PROGRAM: u8 = 0x02;
+    pub const CMD_READ: u8 = 0x03;
+    pub const CMD_WRDI: u8 = 0x04;
+    pub const CMD_RDSR: u8 = 0x05;
+    pub const CMD_WREN: u8 = 0x06;
+    pub const CMD_FAST_READ: u8 = 0x0b;
+    pub const CMD_SECTOR_ERASE: u8 = 0x20;
+
+    /// Status register bit: write in progress.
+    pub const STATUS_WIP: u8 = 0x01;
+    /// Status register bit: write enable latch.
+    pub const STATUS_WEL: u8 = 0x02;
+
+    /// New fully erased chip.
+    ///
+    /// Capacity is rounded up to whole number of sectors. Program and erase
+    /// finish immediately, use [`SpiNorFlash::with_busy_polls`] to emulate
+    /// operation delays.
+    ///
+    /// # Panics
+    ///
+    /// Panics if capacity is zero or exceeds [`SpiNorFlash::MAX_CAPACITY`].
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity != 0, "Capacity must be non-zero.");
+        assert!(
+            capacity <= Self::MAX_CAPACITY,
+            "Capacity exceeds 24-bit address space."
+        );
+        let sectors = capacity.div_ceil(Self::SECTOR_SIZE);
+        Self {
+            data: vec![0xff; sectors * Self::SECTOR_SIZE],
+            write_enabled: false,
+            busy_polls: 0,
+            program_polls: 0,
+            erase_polls: 0,
+        }
+    }
+
+    /// Number of status register polls the chip stays busy after program and
+    /// after erase.
+    pub fn with_busy_polls(self, program_polls: u32, erase_polls: u32) -> Self {
+        Self {
+            program_polls,
+            erase_polls,
+            ..self
+        }
+    }
+
+    /// Chip capacity.
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Raw chip contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Current status register value.
+    pub fn status(&self) -> u8 {
+        let mut status = 0;
+        if self.busy_polls != 0 {
+            status |= Self::STATUS_WIP;
+        }
+        if self.write_enabled {
+            status |= Self::STATUS_WEL;
+        }
+        status
+    }
+
+    /// Perform single chip-select transaction: send `write` bytes (command,
+    /// address and data, if any), then clock in `read.len()` bytes.
+    pub fn transaction(&mut self, write: &[u8], read: &mut [u8]) -> Result<(), SpiNorError> {
+        let command = *write.first().ok_or(SpiNorError::EmptyTransaction)?;
+        if self.busy_polls != 0 && command != Self::CMD_RDSR {
+            return Err(SpiNorError::Busy);
+        }
+        match command {
+            Self::CMD_RDSR => {
+                for byte in read.iter_mut() {
+                    *byte = self.status();
+                    if self.busy_polls != 0 {
+                        self.busy_polls -= 1;
+                        if self.busy_polls == 0 {
+                            self.write_enabled = false;
+                        }
+                    }
+                }
+            }
+            Self::CMD_WREN => self.write_enabled = true,
+            Self::CMD_WRDI => self.write_enabled = false,
+            Self::CMD_READ | Self::CMD_FAST_READ => {
+                let header_len = if command == Self::CMD_READ { 4 } else { 5 };
+                if write.len() < header_len {
+                    return Err(SpiNorError::IncompleteCommand { command });
+                }
+                let address = self.address(write);
+                for (i, byte) in read.iter_mut().enumerate() {
+                    *byte = self.data[(address + i) % self.capacity()];
+                }
+            }
+            Self::CMD_PAGE_PROGRAM => {
+                if write.len() < 4 {
+                    return Err(SpiNorError::IncompleteCommand { command });
+                }
+                self.check_write_enabled()?;
+                let address = self.address(write);
+                let page_start = address - address % Self::PAGE_SIZE;
+                let data = &write[4..];
+                // Only the last page worth of data is kept if more is sent.
+                let skipped = data.len().saturating_sub(Self::PAGE_SIZE);
+                for (i, new) in data.iter().enumerate().skip(skipped) {
+                    let column = (address + i) % Self::PAGE_SIZE;
+                    self.data[page_start + column] &= *new;
+                }
+                self.start_operation(self.program_polls);
+            }
+            Self::CMD_SECTOR_ERASE => {
+                if write.len() < 4 {
+                    return Err(SpiNorError::IncompleteCommand { command });
+                }
+                self.check_write_enabled()?;
+                let address = self.address(write);
+                let sector_start = address - address % Self::SECTOR_SIZE;
+                self.data[sector_start..sector_start + Self::SECTOR_SIZE].fill(0xff);
+                self.start_operation(self.erase_polls);
+            }
+            _ => return Err(SpiNorError::UnknownCommand(command)),
+        }
+        Ok(())
+    }
+
+    /// Region of chip, starting at sector boundary.
+    pub fn region(&self, offset: usize, length: usize) -> Result<SpiNorRegion, BufferError<Self>> {
+        if !offset.is_multiple_of(Self::SECTOR_SIZE) {
+            return Err(BufferError::Misaligned {
+                position: offset,
+                length,
+                alignment: Self::SECTOR_SIZE,
+            });
+        }
+        if offset > self.capacity() || self.capacity() - offset < length {
+            return Err(BufferError::DataTooShort {
+                position: offset,
+                minimal_length: length,
+            });
+        }
+        Ok(SpiNorRegion { offset, length })
+    }
+
+    fn address(&self, write: &[u8]) -> usize {
+        let address = u32::from_be_bytes([0, write[1], write[2], write[3]]) as usize;
+        address % self.capacity()
+    }
+
+    fn check_write_enabled(&self) -> Result<(), SpiNorError> {
+        if self.write_enabled {
+            Ok(())
+        } else {
+            Err(SpiNorError::WriteNotEnabled)
+        }
+    }
+
+    fn start_operation(&mut self, polls: u32) {
+        self.busy_polls = polls;
+        if polls == 0 {
+            self.write_enabled = false;
+        }
+    }
+}
+
+impl ExternalMemory for SpiNorFlash {
+    type ExternalMemoryError = SpiNorError;
+}
+
+/// Improper command sent to [`SpiNorFlash`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpiNorError {
+    Busy,
+    EmptyTransaction,
+    IncompleteCommand { command: u8 },
+    UnknownCommand(u8),
+    WriteNotEnabled,
+}
+
+impl Display for SpiNorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            SpiNorError::Busy => write!(f, "Command sent while flash is busy."),
+            SpiNorError::EmptyTransaction => write!(f, "Transaction has no command."),
+            SpiNorError::IncompleteCommand { command } => {
+                write!(f, "Command {command:#04x} is missing address bytes.")
+            }
+            SpiNorError::UnknownCommand(command) => write!(f, "Unknown command {command:#04x}."),
+            SpiNorError::WriteNotEnabled => {
+                write!(f, "Program or erase command sent without write enable.")
+            }
+        }
+    }
+}
+
+/// Region of [`SpiNorFlash`], accessed through flash commands.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SpiNorRegion {
+    offset: usize,
+    length: usize,
+}
+
+impl SpiNorRegion {
+    fn command(command: u8, address: usize) -> [u8; 4] {
+        let [_, a2, a1, a0] = (address as u32).to_be_bytes();
+        [command, a2, a1, a0]
+    }
+
+    #[cfg(feature = "write")]
+    fn wait_ready(ext_memory: &mut SpiNorFlash) -> Result<(), BufferError<SpiNorFlash>> {
+        let mut status = [SpiNorFlash::STATUS_WIP];
+        while status[0] & SpiNorFlash::STATUS_WIP != 0 {
+            ext_memory
+                .transaction(&[SpiNorFlash::CMD_RDSR], &mut status)
+                .map_err(BufferError::External)?;
+        }
+        Ok(())
+    }
+}
+
+impl AddressableBuffer<SpiNorFlash> for SpiNorRegion {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut SpiNorFlash,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<SpiNorFlash>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut SpiNorFlash,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<SpiNorFlash>> {
+        check_span(self.length, position, dst.len())?;
+        if dst.is_empty() {
+            return Ok(());
+        }
+        ext_memory
+            .transaction(
+                &Self::command(SpiNorFlash::CMD_READ, self.offset + position),
+                dst,
+            )
+            .map_err(BufferError::External)
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<SpiNorFlash>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(feature = "write")]
+impl FlashBuffer<SpiNorFlash> for SpiNorRegion {
+    fn erase_size(&self) -> usize {
+        SpiNorFlash::SECTOR_SIZE
+    }
+    fn program_size(&self) -> usize {
+        1
+    }
+    fn erase_aligned(
+        &mut self,
+        ext_memory: &mut SpiNorFlash,
+        range: Range<usize>,
+    ) -> Result<(), BufferError<SpiNorFlash>> {
+        for sector_start in range.step_by(SpiNorFlash::SECTOR_SIZE) {
+            ext_memory
+                .transaction(&[SpiNorFlash::CMD_WREN], &mut [])
+                .and_then(|_| {
+                    ext_memory.transaction(
+                        &Self::command(SpiNorFlash::CMD_SECTOR_ERASE, self.offset + sector_start),
+                        &mut [],
+                    )
+                })
+                .map_err(BufferError::External)?;
+            Self::wait_ready(ext_memory)?;
+        }
+        Ok(())
+    }
+    fn program_aligned(
+        &mut self,
+        ext_memory: &mut SpiNorFlash,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<SpiNorFlash>> {
+        let mut command = [0; 4 + SpiNorFlash::PAGE_SIZE];
+        let mut done = 0;
+        while done < data.len() {
+            let address = self.offset + position + done;
+            let chunk_len =
+                (SpiNorFlash::PAGE_SIZE - address % SpiNorFlash::PAGE_SIZE).min(data.len() - done);
+            command[..4].copy_from_slice(&Self::command(SpiNorFlash::CMD_PAGE_PROGRAM, address));
+            command[4..4 + chunk_len].copy_from_slice(&data[done..done + chunk_len]);
+            ext_memory
+                .transaction(&[SpiNorFlash::CMD_WREN], &mut [])
+                .and_then(|_| ext_memory.transaction(&command[..4 + chunk_len], &mut []))
+                .map_err(BufferError::External)?;
+            Self::wait_ready(ext_memory)?;
+            done += chunk_len;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program(flash: &mut SpiNorFlash, address: usize, data: &[u8]) {
+        flash
+            .transaction(&[SpiNorFlash::CMD_WREN], &mut [])
+            .unwrap();
+        let mut command = SpiNorRegion::command(SpiNorFlash::CMD_PAGE_PROGRAM, address).to_vec();
+        command.extend_from_slice(data);
+        flash.transaction(&command, &mut []).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must be non-zero.")]
+    fn zero_capacity() {
+        SpiNorFlash::new(0);
+    }
+
+    #[test]
+    fn empty_region_read() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        let region = flash.region(0, 0).unwrap();
+        region.read_into(&mut flash, 0, &mut []).unwrap();
+    }
+
+    #[test]
+    fn region_checks() {
+        let flash = SpiNorFlash::new(2 * SpiNorFlash::SECTOR_SIZE);
+        assert!(matches!(
+            flash.region(256, 16),
+            Err(BufferError::Misaligned { .. })
+        ));
+        assert!(matches!(
+            flash.region(SpiNorFlash::SECTOR_SIZE, SpiNorFlash::SECTOR_SIZE + 1),
+            Err(BufferError::DataTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn program_requires_write_enable() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        let command = SpiNorRegion::command(SpiNorFlash::CMD_PAGE_PROGRAM, 0);
+        assert_eq!(
+            flash.transaction(&command, &mut []),
+            Err(SpiNorError::WriteNotEnabled)
+        );
+        program(&mut flash, 0, &[0x12]);
+        // Latch is reset after the operation.
+        assert_eq!(flash.status(), 0);
+        assert_eq!(
+            flash.transaction(&command, &mut []),
+            Err(SpiNorError::WriteNotEnabled)
+        );
+    }
+
+    #[test]
+    fn program_wraps_within_page() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        program(&mut flash, 254, &[1, 2, 3, 4]);
+        assert_eq!(&flash.contents()[254..258], &[1, 2, 0xff, 0xff]);
+        assert_eq!(&flash.contents()[256..258], &[0xff, 0xff]);
+        assert_eq!(&flash.contents()[0..2], &[3, 4]);
+    }
+
+    #[test]
+    fn program_clears_bits_only() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        program(&mut flash, 0, &[0b1010_1010]);
+        program(&mut flash, 0, &[0b1100_1100]);
+        assert_eq!(flash.contents()[0], 0b1000_1000);
+    }
+
+    #[test]
+    fn erase_sector() {
+        let mut flash = SpiNorFlash::new(2 * SpiNorFlash::SECTOR_SIZE);
+        program(&mut flash, 10, &[0; 4]);
+        program(&mut flash, SpiNorFlash::SECTOR_SIZE, &[0; 4]);
+        flash
+            .transaction(&[SpiNorFlash::CMD_WREN], &mut [])
+            .unwrap();
+        flash
+            .transaction(
+                &SpiNorRegion::command(SpiNorFlash::CMD_SECTOR_ERASE, 100),
+                &mut [],
+            )
+            .unwrap();
+        assert!(flash.contents()[..SpiNorFlash::SECTOR_SIZE]
+            .iter()
+            .all(|a| *a == 0xff));
+        assert_eq!(
+            &flash.contents()[SpiNorFlash::SECTOR_SIZE..SpiNorFlash::SECTOR_SIZE + 4],
+            &[0; 4]
+        );
+    }
+
+    #[test]
+    fn busy_until_polled() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE).with_busy_polls(2, 0);
+        program(&mut flash, 0, &[0]);
+        let mut read = [0; 1];
+        assert_eq!(
+            flash.transaction(&SpiNorRegion::command(SpiNorFlash::CMD_READ, 0), &mut read),
+            Err(SpiNorError::Busy)
+        );
+        let mut status = [0; 3];
+        flash
+            .transaction(&[SpiNorFlash::CMD_RDSR], &mut status)
+            .unwrap();
+        assert_eq!(
+            status,
+            [
+                SpiNorFlash::STATUS_WIP | SpiNorFlash::STATUS_WEL,
+                SpiNorFlash::STATUS_WIP | SpiNorFlash::STATUS_WEL,
+                0
+            ]
+        );
+        flash
+            .transaction(&SpiNorRegion::command(SpiNorFlash::CMD_READ, 0), &mut read)
+            .unwrap();
+        assert_eq!(read, [0]);
+    }
+
+    #[test]
+    fn reads_wrap_at_chip_end() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        program(&mut flash, 0, &[7]);
+        let mut read = [0; 2];
+        flash
+            .transaction(
+                &[SpiNorFlash::CMD_FAST_READ, 0x00, 0x0f, 0xff, 0x00],
+                &mut read,
+            )
+            .unwrap();
+        assert_eq!(read, [0xff, 7]);
+    }
+
+    #[test]
+    fn command_errors() {
+        let mut flash = SpiNorFlash::new(SpiNorFlash::SECTOR_SIZE);
+        assert_eq!(
+            flash.transaction(&[], &mut []),
+            Err(SpiNorError::EmptyTransaction)
+        );
+        assert_eq!(
+            flash.transaction(&[0x9f], &mut []),
+            Err(SpiNorError::UnknownCommand(0x9f))
+        );
+        assert_eq!(
+            flash.transaction(&[SpiNorFlash::CMD_FAST_READ, 0, 0, 0], &mut []),
+            Err(SpiNorError::IncompleteCommand {
+                command: SpiNorFlash::CMD_FAST_READ
+            })
+        );
+    }
+
+    #[cfg(feature = "write")]
+    #[test]
+    fn flash_buffer_over_region() {
+        let mut flash = SpiNorFlash::new(4 * SpiNorFlash::SECTOR_SIZE).with_busy_polls(3, 5);
+        let mut region = flash
+            .region(SpiNorFlash::SECTOR_SIZE, 2 * SpiNorFlash::SECTOR_SIZE)
+            .unwrap();
+        let data: Vec<u8> = (0..600).map(|a| a as u8).collect();
+        region.program(&mut flash, 200, &data).unwrap();
+        assert_eq!(region.read_slice(&mut flash, 200, 600).unwrap(), data);
+        assert_eq!(
+            region.program(&mut flash, 200, &[0]),
+            Err(BufferError::NotErased { position: 200 })
+        );
+        region
+            .erase_range(&mut flash, 0..SpiNorFlash::SECTOR_SIZE)
+            .unwrap();
+        assert!(region
+            .read_slice(&mut flash, 0, 800)
+            .unwrap()
+            .iter()
+            .all(|a| *a == 0xff));
+        assert!(flash.contents()[..SpiNorFlash::SECTOR_SIZE]
+            .iter()
+            .all(|a| *a == 0xff));
+    }
+}
@@ -0,0 +1,308 @@
+//! Bridge to `embedded-storage` traits.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use core::fmt::Debug;
+
+use embedded_storage::{
+    nor_flash::{ErrorType, NorFlashError, NorFlashErrorKind, ReadNorFlash},
+    ReadStorage,
+};
+
+use crate::{check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// Any [`ReadStorage`] used as [`ExternalMemory`].
+///
+/// Storage errors are mapped into [`NorFlashErrorKind`]. Regions of storage
+/// are [`StorageRegion`] values.
+#[derive(Debug)]
+pub struct StorageMemory<S> {
+    storage: S,
+}
+
+impl<S> StorageMemory<S>
+where
+    S: ReadStorage + Debug,
+    S::Error: NorFlashError,
+{
+    /// Wrap storage.
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Wrapped storage.
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Wrapped storage, mutable.
+    pub fn storage_mut(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Release wrapped storage.
+    pub fn into_storage(self) -> S {
+        self.storage
+    }
+
+    /// Region of storage, checked against storage capacity.
+    pub fn region(&self, offset: u32, length: usize) -> Result<StorageRegion, BufferError<Self>> {
+        let total_length = self.storage.capacity();
+        let start = offset as usize;
+        check_span(total_length, start, length)?;
+        Ok(StorageRegion { offset, length })
+    }
+
+    /// Region covering the whole storage.
+    pub fn whole(&self) -> Result<StorageRegion, BufferError<Self>> {
+        self.region(0, self.storage.capacity())
+    }
+}
+
+impl<S> ExternalMemory for StorageMemory<S>
+where
+    S: ReadStorage + Debug,
+    S::Error: NorFlashError,
+{
+    type ExternalMemoryError = NorFlashErrorKind;
+}
+
+/// Region of [`StorageMemory`]: storage offset and region length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct StorageRegion {
+    offset: u32,
+    length: usize,
+}
+
+impl<S> AddressableBuffer<StorageMemory<S>> for StorageRegion
+where
+    S: ReadStorage + Debug,
+    S::Error: NorFlashError,
+{
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut StorageMemory<S>,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<StorageMemory<S>>> {
+        let mut out = vec![0; slice_len];
+        self.read_into(ext_memory, position, &mut out)?;
+        Ok(out)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut StorageMemory<S>,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<StorageMemory<S>>> {
+        check_span(self.length, position, dst.len())?;
+        let offset = u32::try_from(position)
+            .ok()
+            .and_then(|position| self.offset.checked_add(position))
+            .ok_or(BufferError::External(NorFlashErrorKind::OutOfBounds))?;
+        ext_memory
+            .storage
+            .read(offset, dst)
+            .map_err(|e| BufferError::External(e.kind()))
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<StorageMemory<S>>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            offset: self.offset,
+            length: new_len,
+        })
+    }
+}
+
+/// [`AddressableBuffer`] exposed as [`ReadStorage`] and [`ReadNorFlash`].
+#[derive(Debug)]
+pub struct BufferStorage<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> {
+    buffer: &'b B,
+    ext_memory: &'m mut E,
+}
+
+impl<'b, 'm, B: AddressableBuffer<E>, E: ExternalMemory> BufferStorage<'b, 'm, B, E> {
+    /// Expose buffer as storage.
+    pub fn new(buffer: &'b B, ext_memory: &'m mut E) -> Self {
+        Self { buffer, ext_memory }
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> ReadStorage for BufferStorage<'_, '_, B, E> {
+    type Error = BufferError<E>;
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.buffer
+            .read_into(self.ext_memory, offset as usize, bytes)
+    }
+    fn capacity(&self) -> usize {
+        self.buffer.total_len()
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> ErrorType for BufferStorage<'_, '_, B, E> {
+    type Error = BufferError<E>;
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> ReadNorFlash for BufferStorage<'_, '_, B, E> {
+    const READ_SIZE: usize = 1;
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        ReadStorage::read(self, offset, bytes)
+    }
+    fn capacity(&self) -> usize {
+        ReadStorage::capacity(self)
+    }
+}
+
+impl<E: ExternalMemory> NorFlashError for BufferError<E> {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            BufferError::DataTooShort { .. }
+            | BufferError::OutOfRange { .. }
+            | BufferError::WritePastEnd { .. } => NorFlashErrorKind::OutOfBounds,
+            BufferError::Misaligned { .. } => NorFlashErrorKind::NotAligned,
+            _ => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small in-RAM storage, optionally failing all reads.
+    #[derive(Debug)]
+    struct RamStorage {
+        data: [u8; 8],
+        fail: Option<NorFlashErrorKind>,
+    }
+
+    impl RamStorage {
+        fn new() -> Self {
+            Self {
+                data: [0, 1, 2, 3, 4, 5, 6, 7],
+                fail: None,
+            }
+        }
+    }
+
+    impl ReadStorage for RamStorage {
+        type Error = NorFlashErrorKind;
+        fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+            if let Some(kind) = self.fail {
+                return Err(kind);
+            }
+            let start = offset as usize;
+            let part = self
+                .data
+                .get(start..start + bytes.len())
+                .ok_or(NorFlashErrorKind::OutOfBounds)?;
+            bytes.copy_from_slice(part);
+            Ok(())
+        }
+        fn capacity(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    type Memory = StorageMemory<RamStorage>;
+
+    #[test]
+    fn storage_region_reads() {
+        let mut memory = Memory::new(RamStorage::new());
+        let region = memory.region(2, 5).unwrap();
+        assert_eq!(region.read_slice(&mut memory, 1, 3).unwrap(), [3, 4, 5]);
+        assert_eq!(region.read_byte(&mut memory, 4).unwrap(), 6);
+        assert!(matches!(
+            region.read_slice(&mut memory, 3, 3),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 3
+            })
+        ));
+        let limited = AddressableBuffer::<Memory>::limit_length(&region, 2).unwrap();
+        assert_eq!(limited.read_slice(&mut memory, 0, 2).unwrap(), [2, 3]);
+        assert!(AddressableBuffer::<Memory>::limit_length(&region, 6).is_err());
+        assert!(matches!(
+            memory.region(6, 3),
+            Err(BufferError::DataTooShort {
+                position: 6,
+                minimal_length: 3
+            })
+        ));
+        let whole = memory.whole().unwrap();
+        assert_eq!(whole.read_slice(&mut memory, 6, 2).unwrap(), [6, 7]);
+        assert_eq!(memory.into_storage().data[0], 0);
+    }
+
+    #[test]
+    fn storage_errors_mapped() {
+        let mut memory = Memory::new(RamStorage::new());
+        let region = memory.whole().unwrap();
+        for kind in [
+            NorFlashErrorKind::NotAligned,
+            NorFlashErrorKind::OutOfBounds,
+            NorFlashErrorKind::Other,
+        ] {
+            memory.storage_mut().fail = Some(kind);
+            assert!(matches!(
+                region.read_byte(&mut memory, 0),
+                Err(BufferError::External(k)) if k == kind
+            ));
+        }
+    }
+
+    #[test]
+    fn buffer_as_storage() {
+        let data: &[u8] = &[0, 1, 2, 3, 4, 5];
+        let mut ext_memory = ();
+        let mut storage = BufferStorage::new(&data, &mut ext_memory);
+        assert_eq!(ReadStorage::capacity(&storage), 6);
+        assert_eq!(ReadNorFlash::capacity(&storage), 6);
+        let mut bytes = [0; 3];
+        ReadStorage::read(&mut storage, 1, &mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 3]);
+        ReadNorFlash::read(&mut storage, 3, &mut bytes).unwrap();
+        assert_eq!(bytes, [3, 4, 5]);
+        let error = ReadNorFlash::read(&mut storage, 4, &mut bytes).unwrap_err();
+        assert_eq!(
+            error,
+            BufferError::DataTooShort {
+                position: 4,
+                minimal_length: 3
+            }
+        );
+        assert_eq!(error.kind(), NorFlashErrorKind::OutOfBounds);
+        let error = ReadStorage::read(&mut storage, 7, &mut bytes).unwrap_err();
+        assert_eq!(error.kind(), NorFlashErrorKind::OutOfBounds);
+    }
+
+    #[test]
+    fn buffer_error_kinds() {
+        let misaligned = BufferError::<()>::Misaligned {
+            position: 1,
+            length: 2,
+            alignment: 4,
+        };
+        assert_eq!(misaligned.kind(), NorFlashErrorKind::NotAligned);
+        let past_end = BufferError::<()>::WritePastEnd {
+            position: 1,
+            write_length: 8,
+            total_length: 4,
+        };
+        assert_eq!(past_end.kind(), NorFlashErrorKind::OutOfBounds);
+        let external = BufferError::<Memory>::External(NorFlashErrorKind::NotAligned);
+        assert_eq!(external.kind(), NorFlashErrorKind::Other);
+    }
+}
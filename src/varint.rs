@@ -0,0 +1,372 @@
+//! Variable-length integers: SCALE compact and LEB128.
+use crate::{AddressableBuffer, BufferError, ExternalMemory};
+
+/// Decoded value and number of bytes it occupied in the buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Decoded<T> {
+    pub value: T,
+    pub length: usize,
+}
+
+/// Decode SCALE compact integer at known position.
+///
+/// Target type `T` could be any of `u8` to `u128`. Values not fitting into
+/// `T` result in [`BufferError::OverlongEncoding`], values encoded with more
+/// bytes than necessary result in [`BufferError::NonCanonicalEncoding`].
+pub fn decode_compact<T, B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    position: usize,
+) -> Result<Decoded<T>, BufferError<E>>
+where
+    T: TryFrom<u128>,
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    let first = buffer.read_byte(ext_memory, position)?;
+    let (value, length) = match first & 0b11 {
+        0b00 => ((first >> 2) as u128, 1),
+        0b01 => {
+            let value = buffer.read_u16_le(ext_memory, position)? >> 2;
+            if value <= 0x3f {
+                return Err(BufferError::NonCanonicalEncoding { position });
+            }
+            (value as u128, 2)
+        }
+        0b10 => {
+            let value = buffer.read_u32_le(ext_memory, position)? >> 2;
+            if value <= 0x3fff {
+                return Err(BufferError::NonCanonicalEncoding { position });
+            }
+            (value as u128, 4)
+        }
+        _ => {
+            let number_len = (first >> 2) as usize + 4;
+            if number_len > 16 {
+                return Err(BufferError::OverlongEncoding { position });
+            }
+            let mut bytes = [0; 16];
+            buffer.read_into(ext_memory, position + 1, &mut bytes[..number_len])?;
+            let value = u128::from_le_bytes(bytes);
+            if bytes[number_len - 1] == 0 || value <= 0x3fff_ffff {
+                return Err(BufferError::NonCanonicalEncoding { position });
+            }
+            (value, number_len + 1)
+        }
+    };
+    let value = T::try_from(value).map_err(|_| BufferError::OverlongEncoding { position })?;
+    Ok(Decoded { value, length })
+}
+
+/// Decode unsigned LEB128 integer at known position.
+///
+/// Target type `T` could be any of `u8` to `u128`. Values not fitting into
+/// `T` result in [`BufferError::OverlongEncoding`], encodings with trailing
+/// zero bytes result in [`BufferError::NonCanonicalEncoding`].
+pub fn decode_uleb128<T, B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    position: usize,
+) -> Result<Decoded<T>, BufferError<E>>
+where
+    T: TryFrom<u128>,
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    let mut value: u128 = 0;
+    let mut shift = 0;
+    let mut length = 0;
+    loop {
+        if shift >= u128::BITS {
+            return Err(BufferError::OverlongEncoding { position });
+        }
+        let byte = buffer.read_byte(ext_memory, position + length)?;
+        length += 1;
+        let low = (byte & 0x7f) as u128;
+        if shift > u128::BITS - 7 && low >> (u128::BITS - shift) != 0 {
+            return Err(BufferError::OverlongEncoding { position });
+        }
+        value |= low << shift;
+        if byte & 0x80 == 0 {
+            if length > 1 && byte == 0 {
+                return Err(BufferError::NonCanonicalEncoding { position });
+            }
+            break;
+        }
+        shift += 7;
+    }
+    let value = T::try_from(value).map_err(|_| BufferError::OverlongEncoding { position })?;
+    Ok(Decoded { value, length })
+}
+
+/// Decode signed LEB128 integer at known position.
+///
+/// Target type `T` could be any of `i8` to `i128`. Values not fitting into
+/// `T` result in [`BufferError::OverlongEncoding`], encodings with redundant
+/// sign extension bytes result in [`BufferError::NonCanonicalEncoding`].
+pub fn decode_sleb128<T, B, E>(
+    buffer: &B,
+    ext_memory: &mut E,
+    position: usize,
+) -> Result<Decoded<T>, BufferError<E>>
+where
+    T: TryFrom<i128>,
+    B: AddressableBuffer<E>,
+    E: ExternalMemory,
+{
+    let mut value: i128 = 0;
+    let mut shift = 0;
+    let mut length = 0;
+    let mut previous_byte = None;
+    loop {
+        if shift >= i128::BITS {
+            return Err(BufferError::OverlongEncoding { position });
+        }
+        let byte = buffer.read_byte(ext_memory, position + length)?;
+        length += 1;
+        let low = (byte & 0x7f) as i128;
+        if shift > i128::BITS - 7 {
+            // Bits that do not fit must be a copy of the sign bit.
+            let extension = low >> (i128::BITS - shift - 1);
+            if extension != 0 && extension != 0x7f >> (i128::BITS - shift - 1) {
+                return Err(BufferError::OverlongEncoding { position });
+            }
+        }
+        value |= low << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            if let Some(previous_byte) = previous_byte {
+                let previous_sign = previous_byte & 0x40 != 0;
+                if (byte == 0 && !previous_sign) || (byte == 0x7f && previous_sign) {
+                    return Err(BufferError::NonCanonicalEncoding { position });
+                }
+            }
+            if shift < i128::BITS && byte & 0x40 != 0 {
+                value |= -1 << shift;
+            }
+            break;
+        }
+        previous_byte = Some(byte);
+    }
+    let value = T::try_from(value).map_err(|_| BufferError::OverlongEncoding { position })?;
+    Ok(Decoded { value, length })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compact<T: TryFrom<u128>>(data: &[u8]) -> Result<Decoded<T>, BufferError<()>> {
+        decode_compact(&data, &mut (), 0)
+    }
+
+    fn uleb<T: TryFrom<u128>>(data: &[u8]) -> Result<Decoded<T>, BufferError<()>> {
+        decode_uleb128(&data, &mut (), 0)
+    }
+
+    fn sleb<T: TryFrom<i128>>(data: &[u8]) -> Result<Decoded<T>, BufferError<()>> {
+        decode_sleb128(&data, &mut (), 0)
+    }
+
+    #[test]
+    fn compact_modes() {
+        assert_eq!(
+            compact::<u8>(&[0x00]),
+            Ok(Decoded {
+                value: 0,
+                length: 1
+            })
+        );
+        assert_eq!(
+            compact::<u8>(&[0xfc]),
+            Ok(Decoded {
+                value: 63,
+                length: 1
+            })
+        );
+        assert_eq!(
+            compact::<u16>(&[0x01, 0x01]),
+            Ok(Decoded {
+                value: 64,
+                length: 2
+            })
+        );
+        assert_eq!(
+            compact::<u32>(&[0x02, 0x00, 0x01, 0x00]),
+            Ok(Decoded {
+                value: 1 << 14,
+                length: 4
+            })
+        );
+        assert_eq!(
+            compact::<u32>(&[0x03, 0x00, 0x00, 0x00, 0x40]),
+            Ok(Decoded {
+                value: 1 << 30,
+                length: 5
+            })
+        );
+        assert_eq!(
+            compact::<u128>(&[0x33; 17]),
+            Ok(Decoded {
+                value: u128::from_le_bytes([0x33; 16]),
+                length: 17
+            })
+        );
+    }
+
+    #[test]
+    fn compact_non_canonical() {
+        let error = BufferError::NonCanonicalEncoding { position: 0 };
+        assert_eq!(compact::<u8>(&[0x01, 0x00]).unwrap_err(), error);
+        assert_eq!(
+            compact::<u16>(&[0x02, 0x01, 0x00, 0x00]).unwrap_err(),
+            error
+        );
+        assert_eq!(
+            compact::<u32>(&[0x03, 0xff, 0xff, 0xff, 0x3f]).unwrap_err(),
+            error
+        );
+        assert_eq!(
+            compact::<u64>(&[0x07, 0, 0, 0, 0x40, 0x00]).unwrap_err(),
+            error
+        );
+    }
+
+    #[test]
+    fn compact_overlong() {
+        let error = BufferError::OverlongEncoding { position: 0 };
+        assert_eq!(compact::<u8>(&[0x01, 0x04]).unwrap_err(), error);
+        assert_eq!(compact::<u128>(&[0x37; 18]).unwrap_err(), error);
+    }
+
+    #[test]
+    fn compact_too_short() {
+        assert!(matches!(
+            compact::<u32>(&[0x02, 0x00]),
+            Err(BufferError::DataTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn uleb128_values() {
+        assert_eq!(
+            uleb::<u8>(&[0x00]),
+            Ok(Decoded {
+                value: 0,
+                length: 1
+            })
+        );
+        assert_eq!(
+            uleb::<u32>(&[0xe5, 0x8e, 0x26]),
+            Ok(Decoded {
+                value: 624_485,
+                length: 3
+            })
+        );
+        let mut max = [0xff; 19];
+        max[18] = 0x03;
+        assert_eq!(
+            uleb::<u128>(&max),
+            Ok(Decoded {
+                value: u128::MAX,
+                length: 19
+            })
+        );
+    }
+
+    #[test]
+    fn uleb128_errors() {
+        assert_eq!(
+            uleb::<u32>(&[0x80, 0x00]),
+            Err(BufferError::NonCanonicalEncoding { position: 0 })
+        );
+        assert_eq!(
+            uleb::<u8>(&[0x80, 0x02]),
+            Err(BufferError::OverlongEncoding { position: 0 })
+        );
+        let mut too_wide = [0xff; 19];
+        too_wide[18] = 0x04;
+        assert_eq!(
+            uleb::<u128>(&too_wide),
+            Err(BufferError::OverlongEncoding { position: 0 })
+        );
+        assert_eq!(
+            uleb::<u128>(&[0x80; 20]),
+            Err(BufferError::OverlongEncoding { position: 0 })
+        );
+        assert_eq!(
+            uleb::<u32>(&[0x80, 0x80]),
+            Err(BufferError::DataTooShort {
+                position: 2,
+                minimal_length: 1
+            })
+        );
+    }
+
+    #[test]
+    fn sleb128_values() {
+        assert_eq!(
+            sleb::<i8>(&[0x02]),
+            Ok(Decoded {
+                value: 2,
+                length: 1
+            })
+        );
+        assert_eq!(
+            sleb::<i8>(&[0x7e]),
+            Ok(Decoded {
+                value: -2,
+                length: 1
+            })
+        );
+        assert_eq!(
+            sleb::<i32>(&[0xc0, 0xbb, 0x78]),
+            Ok(Decoded {
+                value: -123_456,
+                length: 3
+            })
+        );
+        assert_eq!(
+            sleb::<i16>(&[0x80, 0x7f]),
+            Ok(Decoded {
+                value: -128,
+                length: 2
+            })
+        );
+        let mut min = [0x80; 19];
+        min[18] = 0x7e;
+        assert_eq!(
+            sleb::<i128>(&min),
+            Ok(Decoded {
+                value: i128::MIN,
+                length: 19
+            })
+        );
+    }
+
+    #[test]
+    fn sleb128_errors() {
+        let non_canonical = BufferError::NonCanonicalEncoding { position: 0 };
+        assert_eq!(sleb::<i32>(&[0x82, 0x00]).unwrap_err(), non_canonical);
+        assert_eq!(sleb::<i32>(&[0xfe, 0x7f]).unwrap_err(), non_canonical);
+        assert_eq!(
+            sleb::<i8>(&[0x80, 0x01]),
+            Err(BufferError::OverlongEncoding { position: 0 })
+        );
+        let mut too_wide = [0x80; 19];
+        too_wide[18] = 0x7d;
+        assert_eq!(
+            sleb::<i128>(&too_wide),
+            Err(BufferError::OverlongEncoding { position: 0 })
+        );
+    }
+
+    #[test]
+    fn position_is_reported() {
+        let data = [0xff, 0x80, 0x00];
+        assert_eq!(
+            decode_uleb128::<u32, _, ()>(&data.as_slice(), &mut (), 1),
+            Err(BufferError::NonCanonicalEncoding { position: 1 })
+        );
+    }
+}
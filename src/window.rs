@@ -0,0 +1,198 @@
+//! Offset windows into addressable buffers.
+use core::ops::Range;
+
+use crate::{check_range, check_span, AddressableBuffer, BufferError, ExternalMemory};
+
+/// View into parent [`AddressableBuffer`], starting at an offset.
+///
+/// Positions in window are relative to window start. Window is itself an
+/// `AddressableBuffer`, so it could be passed to any code expecting one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Window<B> {
+    parent: B,
+    start: usize,
+    length: usize,
+}
+
+impl<B> Window<B> {
+    /// New window over `range` of `parent` buffer.
+    ///
+    /// Range is checked against the parent total length.
+    pub fn new<E: ExternalMemory>(parent: B, range: Range<usize>) -> Result<Self, BufferError<E>>
+    where
+        B: AddressableBuffer<E>,
+    {
+        check_range(parent.total_len(), &range)?;
+        Ok(Self {
+            parent,
+            start: range.start,
+            length: range.len(),
+        })
+    }
+
+    /// Window start position in parent buffer.
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Parent buffer.
+    pub fn parent(&self) -> &B {
+        &self.parent
+    }
+
+    /// Release parent buffer.
+    pub fn into_parent(self) -> B {
+        self.parent
+    }
+}
+
+impl<B: AddressableBuffer<E>, E: ExternalMemory> AddressableBuffer<E> for Window<B> {
+    type ReadBuffer = B::ReadBuffer;
+    fn total_len(&self) -> usize {
+        self.length
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        check_span(self.length, position, slice_len)?;
+        self.parent
+            .read_slice(ext_memory, self.start + position, slice_len)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        check_span(self.length, position, dst.len())?;
+        self.parent
+            .read_into(ext_memory, self.start + position, dst)
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        if new_len > self.length {
+            return Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: new_len,
+            });
+        }
+        Ok(Self {
+            parent: self.parent.limit_length(self.start + new_len)?,
+            start: self.start,
+            length: new_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    #[test]
+    fn sub_buffer_reads() {
+        let window = AddressableBuffer::<()>::sub_buffer(DATA, 2..8).unwrap();
+        assert_eq!(window.start(), 2);
+        assert_eq!(AddressableBuffer::<()>::total_len(&window), 6);
+        assert_eq!(window.read_slice(&mut (), 1, 3), Ok(&DATA[3..6]));
+        assert_eq!(window.read_byte(&mut (), 5), Ok(7));
+        assert_eq!(
+            window.read_slice(&mut (), 4, 3),
+            Err(BufferError::DataTooShort {
+                position: 4,
+                minimal_length: 3
+            })
+        );
+        assert_eq!(
+            window.read_slice(&mut (), 7, 0),
+            Err(BufferError::OutOfRange {
+                position: 7,
+                total_length: 6
+            })
+        );
+        assert_eq!(window.into_parent(), DATA);
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn range_checks() {
+        assert_eq!(
+            Window::<&[u8]>::new::<()>(DATA, 5..4),
+            Err(BufferError::InvalidRange { start: 5, end: 4 })
+        );
+        assert_eq!(
+            Window::<&[u8]>::new::<()>(DATA, 11..12),
+            Err(BufferError::OutOfRange {
+                position: 11,
+                total_length: 10
+            })
+        );
+        assert_eq!(
+            Window::<&[u8]>::new::<()>(DATA, 8..11),
+            Err(BufferError::DataTooShort {
+                position: 8,
+                minimal_length: 3
+            })
+        );
+        let empty = Window::<&[u8]>::new::<()>(DATA, 10..10).unwrap();
+        assert_eq!(AddressableBuffer::<()>::total_len(&empty), 0);
+    }
+
+    #[test]
+    fn split_at_mid() {
+        let (first, second) = AddressableBuffer::<()>::split_at(DATA, 4).unwrap();
+        assert_eq!(first.read_slice(&mut (), 0, 4), Ok(&DATA[..4]));
+        assert_eq!(second.start(), 4);
+        assert_eq!(second.read_slice(&mut (), 0, 6), Ok(&DATA[4..]));
+        assert_eq!(
+            AddressableBuffer::<()>::split_at(DATA, 11).map(|_| ()),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 11
+            })
+        );
+    }
+
+    #[test]
+    fn nested_windows() {
+        let outer = AddressableBuffer::<()>::sub_buffer(DATA, 2..9).unwrap();
+        let inner = AddressableBuffer::<()>::sub_buffer(outer, 1..5).unwrap();
+        assert_eq!(inner.read_slice(&mut (), 0, 4), Ok(&DATA[3..7]));
+        assert_eq!(
+            AddressableBuffer::<()>::sub_buffer(outer, 3..8).map(|_| ()),
+            Err(BufferError::DataTooShort {
+                position: 3,
+                minimal_length: 5
+            })
+        );
+    }
+
+    #[test]
+    fn nested_limit_length() {
+        let outer = AddressableBuffer::<()>::sub_buffer(DATA, 2..9).unwrap();
+        let inner = AddressableBuffer::<()>::sub_buffer(outer, 1..5).unwrap();
+        let limited = AddressableBuffer::<()>::limit_length(&inner, 2).unwrap();
+        assert_eq!(AddressableBuffer::<()>::total_len(&limited), 2);
+        assert_eq!(limited.read_slice(&mut (), 0, 2), Ok(&DATA[3..5]));
+        assert_eq!(
+            limited.read_slice(&mut (), 1, 2),
+            Err(BufferError::DataTooShort {
+                position: 1,
+                minimal_length: 2
+            })
+        );
+        // Parents are limited as well, down to the end of the window.
+        assert_eq!(AddressableBuffer::<()>::total_len(limited.parent()), 3);
+        assert_eq!(limited.parent().parent().len(), 5);
+        assert_eq!(
+            AddressableBuffer::<()>::limit_length(&inner, 5),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 5
+            })
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Write access to external memory.
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+use crate::{AddressableBuffer, BufferError, ExternalMemory};
+
+/// Bytes writing through [`ExternalMemory`].
+///
+/// Counterpart of [`AddressableBuffer`]; could be implemented for the same
+/// combination of an address in external memory and bytes slice length.
+pub trait WritableBuffer<E: ExternalMemory>: Sized {
+    /// Write bytes slice at known relative position.
+    ///
+    /// Similarly to `read_slice`, `write_slice` is the basic writer tool,
+    /// because of commonly occuring pages in memory.
+    fn write_slice(
+        &mut self,
+        ext_memory: &mut E,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>>;
+
+    /// Write single byte at known position.
+    fn write_byte(
+        &mut self,
+        ext_memory: &mut E,
+        position: usize,
+        byte: u8,
+    ) -> Result<(), BufferError<E>> {
+        self.write_slice(ext_memory, position, &[byte])
+    }
+
+    /// Make sure all previously written data has reached the memory.
+    ///
+    /// Does nothing by default, for memory without write caching.
+    fn flush(&mut self, _ext_memory: &mut E) -> Result<(), BufferError<E>> {
+        Ok(())
+    }
+}
+
+/// Buffer supporting both reading and writing.
+///
+/// Automatically implemented for all types implementing both
+/// [`AddressableBuffer`] and [`WritableBuffer`].
+pub trait ReadWriteBuffer<E: ExternalMemory>: AddressableBuffer<E> + WritableBuffer<E> {}
+
+impl<E: ExternalMemory, T: AddressableBuffer<E> + WritableBuffer<E>> ReadWriteBuffer<E> for T {}
+
+/// `WritableBuffer` could be also implemented for regular mutable bytes
+/// slices.
+impl<E: ExternalMemory> WritableBuffer<E> for &mut [u8] {
+    fn write_slice(
+        &mut self,
+        _ext_memory: &mut E,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>> {
+        let total_length = self.len();
+        match position
+            .checked_add(data.len())
+            .and_then(|end| self.get_mut(position..end))
+        {
+            Some(a) => {
+                a.copy_from_slice(data);
+                Ok(())
+            }
+            None => Err(BufferError::WritePastEnd {
+                position,
+                write_length: data.len(),
+                total_length,
+            }),
+        }
+    }
+}
+
+/// Owned bytes in RAM, readable and writable through any [`ExternalMemory`],
+/// typically `()`.
+///
+/// Useful as [`ReadWriteBuffer`] for layers that need both reading and
+/// writing, and for tests. `limit_length` copies the kept part.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RamBuffer {
+    data: Vec<u8>,
+}
+
+impl RamBuffer {
+    /// Buffer of known length, filled with zeroes.
+    pub fn new(len: usize) -> Self {
+        Self { data: vec![0; len] }
+    }
+
+    /// Buffer contents.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Release buffer contents.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl From<Vec<u8>> for RamBuffer {
+    fn from(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+}
+
+impl<E: ExternalMemory> AddressableBuffer<E> for RamBuffer {
+    type ReadBuffer = Vec<u8>;
+    fn total_len(&self) -> usize {
+        self.data.len()
+    }
+    fn read_slice(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        slice_len: usize,
+    ) -> Result<Self::ReadBuffer, BufferError<E>> {
+        self.data
+            .as_slice()
+            .read_slice(ext_memory, position, slice_len)
+            .map(<[u8]>::to_vec)
+    }
+    fn read_into(
+        &self,
+        ext_memory: &mut E,
+        position: usize,
+        dst: &mut [u8],
+    ) -> Result<(), BufferError<E>> {
+        self.data.as_slice().read_into(ext_memory, position, dst)
+    }
+    fn limit_length(&self, new_len: usize) -> Result<Self, BufferError<E>> {
+        AddressableBuffer::<E>::limit_length(&self.data.as_slice(), new_len)
+            .map(|a| Self { data: a.to_vec() })
+    }
+}
+
+impl<E: ExternalMemory> WritableBuffer<E> for RamBuffer {
+    fn write_slice(
+        &mut self,
+        ext_memory: &mut E,
+        position: usize,
+        data: &[u8],
+    ) -> Result<(), BufferError<E>> {
+        self.data
+            .as_mut_slice()
+            .write_slice(ext_memory, position, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_into_mut_slice() {
+        let mut data = [0; 6];
+        let mut buffer = data.as_mut_slice();
+        buffer.write_slice(&mut (), 1, &[1, 2]).unwrap();
+        buffer.write_byte(&mut (), 5, 3).unwrap();
+        buffer.write_slice(&mut (), 6, &[]).unwrap();
+        assert_eq!(buffer.flush(&mut ()), Ok(()));
+        assert_eq!(
+            buffer.write_slice(&mut (), 5, &[4, 5]),
+            Err(BufferError::WritePastEnd {
+                position: 5,
+                write_length: 2,
+                total_length: 6
+            })
+        );
+        assert_eq!(
+            buffer.write_byte(&mut (), 6, 4),
+            Err(BufferError::WritePastEnd {
+                position: 6,
+                write_length: 1,
+                total_length: 6
+            })
+        );
+        assert_eq!(data, [0, 1, 2, 0, 0, 3]);
+    }
+
+    #[test]
+    fn ram_buffer_round_trip() {
+        let mut buffer = RamBuffer::new(8);
+        buffer.write_slice(&mut (), 2, &[1, 2, 3]).unwrap();
+        buffer.write_byte(&mut (), 7, 9).unwrap();
+        assert_eq!(buffer.as_slice(), [0, 0, 1, 2, 3, 0, 0, 9]);
+        assert_eq!(buffer.read_slice(&mut (), 3, 2), Ok(vec![2, 3]));
+        assert_eq!(buffer.read_byte(&mut (), 7), Ok(9));
+        let limited = AddressableBuffer::<()>::limit_length(&buffer, 4).unwrap();
+        assert_eq!(limited.into_inner(), [0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn ram_buffer_bounds() {
+        let mut buffer = RamBuffer::from(vec![0; 4]);
+        assert_eq!(
+            buffer.write_slice(&mut (), 2, &[1, 2, 3]),
+            Err(BufferError::WritePastEnd {
+                position: 2,
+                write_length: 3,
+                total_length: 4
+            })
+        );
+        assert_eq!(
+            buffer.write_slice(&mut (), usize::MAX, &[1]),
+            Err(BufferError::WritePastEnd {
+                position: usize::MAX,
+                write_length: 1,
+                total_length: 4
+            })
+        );
+        assert_eq!(buffer.as_slice(), [0; 4]);
+        assert_eq!(
+            buffer.read_slice(&mut (), 5, 0),
+            Err(BufferError::OutOfRange {
+                position: 5,
+                total_length: 4
+            })
+        );
+        assert_eq!(
+            AddressableBuffer::<()>::limit_length(&buffer, 5),
+            Err(BufferError::DataTooShort {
+                position: 0,
+                minimal_length: 5
+            })
+        );
+    }
+}